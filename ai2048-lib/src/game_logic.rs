@@ -1,6 +1,8 @@
 //! 2048 game logic is implemented here.
 use lazy_static::lazy_static;
 use rand::{self, Rng};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryInto;
 use std::{fmt, u16};
 
 /// Represents a move.
@@ -119,6 +121,59 @@ impl fmt::Debug for Grid {
     }
 }
 
+/// Serializes as the raw bitboard `u64` for compact, binary formats, or as the
+/// `[[u32; 4]; 4]` human-readable matrix (see `unpack_human`) for self-describing formats such
+/// as JSON.
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            self.unpack_human().serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let human = <[[u32; 4]; 4]>::deserialize(deserializer)?;
+            Grid::from_human(human).ok_or_else(|| {
+                de::Error::custom("tile is not zero or a power of two up to 32768")
+            })
+        } else {
+            let bits = u64::deserialize(deserializer)?;
+            Ok(Grid(bits))
+        }
+    }
+}
+
+/// Configurable game rules, so callers can model common 2048 variants instead of only the
+/// classic game: a different spawn distribution, a tile cap (changing what value wins the
+/// game), or an alternate merge predicate.
+pub struct Rules {
+    /// Probability (in `[0, 1]`) that a newly spawned tile is a `4` rather than a `2`. The
+    /// classic game uses `0.1`.
+    pub four_spawn_probability: f64,
+    /// The highest tile log2 exponent that's allowed to exist on the board; merges that would
+    /// produce a higher one are suppressed, so the two tiles involved simply don't merge. The
+    /// classic game effectively has no reachable cap, so this defaults to `15` (`32768`).
+    pub max_tile_log: u8,
+    /// An alternate merge predicate, given the log2 exponent of the two equal tiles about to
+    /// merge. Defaults to always allowing the merge.
+    pub can_merge: fn(u8) -> bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            four_spawn_probability: 0.1,
+            max_tile_log: 15,
+            can_merge: |_| true,
+        }
+    }
+}
+
 fn to_log(n: u32) -> Option<u8> {
     use std::f32;
 
@@ -167,6 +222,68 @@ impl Grid {
         result
     }
 
+    /// Returns the tile value (`0`, `2`, `4`, `8`, ...) at `(row, col)`, without allocating.
+    pub fn tile_at(self, row: usize, col: usize) -> u32 {
+        let log = self.rows()[row].unpack()[col];
+        if log == 0 {
+            0
+        } else {
+            1 << log
+        }
+    }
+
+    /// Returns a new `Grid` with the tile at `(row, col)` set to `value`. Returns `None` if
+    /// `value` isn't zero or a power of two up to `32768`.
+    pub fn with_tile(self, row: usize, col: usize, value: u32) -> Option<Grid> {
+        if value != 0 && !value.is_power_of_two() {
+            return None;
+        }
+        let log = to_log(value)?;
+        let mut grid = self.unpack_log();
+        grid[row][col] = log;
+        Grid::from_log(grid)
+    }
+
+    /// Iterates over the `(row, col)` positions of every empty tile.
+    pub fn empty_positions(self) -> impl Iterator<Item = (usize, usize)> {
+        let grid = self.unpack_log();
+        (0..4usize).flat_map(move |row| {
+            (0..4usize).filter_map(move |col| {
+                if grid[row][col] == 0 {
+                    Some((row, col))
+                } else {
+                    None
+                }
+            })
+        })
+    }
+
+    /// Iterates over the four tile values of `row`, directly off the packed nibbles.
+    pub fn row_iter(self, row: usize) -> impl Iterator<Item = u32> {
+        let unpacked = self.rows()[row].unpack();
+        (0..4).map(move |col| {
+            let log = unpacked[col];
+            if log == 0 {
+                0
+            } else {
+                1 << log
+            }
+        })
+    }
+
+    /// Iterates over the four tile values of `col`, directly off the packed nibbles.
+    pub fn col_iter(self, col: usize) -> impl Iterator<Item = u32> {
+        let unpacked = self.transpose().rows()[col].unpack();
+        (0..4).map(move |row| {
+            let log = unpacked[row];
+            if log == 0 {
+                0
+            } else {
+                1 << log
+            }
+        })
+    }
+
     fn from_log(grid: [[u8; 4]; 4]) -> Option<Grid> {
         let mut rows = [Row::default(); 4];
         for (x, &row) in grid.iter().enumerate() {
@@ -234,6 +351,75 @@ impl Grid {
         Grid::from_log(grid).unwrap()
     }
 
+    /// As `add_random_tile`, but following `rules`' spawn distribution and taking an explicit
+    /// `Rng` instead of an internal `rand::thread_rng()`. This makes board generation
+    /// reproducible from a seed, which is useful for deterministic tests and AI benchmarking
+    /// over fixed game streams.
+    pub fn add_random_tile_with(self, rules: &Rules, rng: &mut impl Rng) -> Grid {
+        let mut grid = self.unpack_log();
+        let empty_tile_count = grid.iter().flatten().filter(|v| **v == 0).count();
+        let position = rng.gen_range(0, empty_tile_count);
+
+        let value = grid
+            .iter_mut()
+            .flatten()
+            .filter(|v| **v == 0)
+            .nth(position)
+            .unwrap();
+
+        *value = if rng.gen_bool(rules.four_spawn_probability) {
+            2
+        } else {
+            1
+        };
+
+        Grid::from_log(grid).unwrap()
+    }
+
+    /// As `make_move`, but using `rules`' merge predicate and tile cap instead of the classic,
+    /// fixed rule. Unlike `make_move`, this isn't backed by the precomputed row-move caches,
+    /// since those are baked in for the classic rules.
+    pub fn make_move_with_rules(self, mv: Move, rules: &Rules) -> Grid {
+        match mv {
+            Move::Left => {
+                let rows = self.rows();
+                Grid::from_rows([
+                    move_row_left_with_rules(rows[0], rules),
+                    move_row_left_with_rules(rows[1], rules),
+                    move_row_left_with_rules(rows[2], rules),
+                    move_row_left_with_rules(rows[3], rules),
+                ])
+            }
+            Move::Right => {
+                let rows = self.rows();
+                Grid::from_rows([
+                    move_row_left_with_rules(rows[0].reverse(), rules).reverse(),
+                    move_row_left_with_rules(rows[1].reverse(), rules).reverse(),
+                    move_row_left_with_rules(rows[2].reverse(), rules).reverse(),
+                    move_row_left_with_rules(rows[3].reverse(), rules).reverse(),
+                ])
+            }
+            Move::Up => {
+                let rows = self.transpose().rows();
+                Grid::from_columns([
+                    Column::from_row(move_row_left_with_rules(rows[0], rules)),
+                    Column::from_row(move_row_left_with_rules(rows[1], rules)),
+                    Column::from_row(move_row_left_with_rules(rows[2], rules)),
+                    Column::from_row(move_row_left_with_rules(rows[3], rules)),
+                ])
+            }
+            Move::Down => {
+                let rows = self.transpose().rows();
+                Grid::from_columns([
+                    Column::from_row(move_row_left_with_rules(rows[0].reverse(), rules).reverse()),
+                    Column::from_row(move_row_left_with_rules(rows[1].reverse(), rules).reverse()),
+                    Column::from_row(move_row_left_with_rules(rows[2].reverse(), rules).reverse()),
+                    Column::from_row(move_row_left_with_rules(rows[3].reverse(), rules).reverse()),
+                ])
+            }
+        }
+    }
+
     pub(crate) fn ai_moves_with2(self) -> impl Iterator<Item = Grid> {
         AiMoves::new(self, 1)
     }
@@ -292,6 +478,32 @@ impl Grid {
         }
     }
 
+    /// Returns the `Grid` that would result from making a certain `Move`, together with the
+    /// score awarded by the merges that move performed, following the standard 2048 rule that
+    /// merging two `2^v` tiles into a `2^(v+1)` tile adds `2^(v+1)` to the score.
+    ///
+    /// A move that doesn't change the board is illegal and scores `0`.
+    pub fn make_move_scored(self, mv: Move) -> (Grid, u32) {
+        let new_grid = self.make_move(mv);
+        if new_grid == self {
+            return (new_grid, 0);
+        }
+
+        let rows = match mv {
+            Move::Left | Move::Right => self.rows(),
+            Move::Up | Move::Down => self.transpose().rows(),
+        };
+
+        let lookup_score = match mv {
+            Move::Left | Move::Up => lookup_score_left,
+            Move::Right | Move::Down => lookup_score_right,
+        };
+
+        let score = rows.iter().map(|&row| lookup_score(row)).sum();
+
+        (new_grid, score)
+    }
+
     fn move_left(self) -> Grid {
         let rows = self.rows();
         let row0 = lookup_left(rows[0]);
@@ -328,6 +540,45 @@ impl Grid {
         Grid::from_columns([col0, col1, col2, col3])
     }
 
+    /// Packs this `Grid` into its 8-byte little-endian bitboard representation, for cheap
+    /// storage or transmission.
+    pub fn to_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+
+    /// Unpacks a `Grid` from its 8-byte little-endian bitboard representation, as produced by
+    /// `to_bytes`. Every nibble is a valid tile log, so this never fails today, but returns
+    /// `Option` for symmetry with `from_human` and to leave room for future validation.
+    pub fn from_bytes(bytes: [u8; 8]) -> Option<Grid> {
+        Some(Grid(u64::from_le_bytes(bytes)))
+    }
+
+    /// Exposes this `Grid`'s raw packed bit pattern, so `sized::Board<4>` -- whose nibble layout
+    /// is defined to match `Grid`'s exactly -- can delegate to these cached implementations
+    /// instead of duplicating them.
+    pub(crate) fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Wraps a raw packed bit pattern as a `Grid`, with no validation: as with `from_bytes`,
+    /// every nibble is already a valid tile log, so this can't actually fail today.
+    pub(crate) fn from_bits(bits: u64) -> Grid {
+        Grid(bits)
+    }
+
+    /// Encodes this `Grid` as a short, URL-safe base64 string (the `to_bytes` representation).
+    pub fn to_base64(self) -> String {
+        base64::encode_config(self.to_bytes(), base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Decodes a `Grid` from a string produced by `to_base64`. Returns `None` if the string
+    /// isn't valid base64 or doesn't decode to exactly 8 bytes.
+    pub fn from_base64(encoded: &str) -> Option<Grid> {
+        let bytes = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).ok()?;
+        let bytes: [u8; 8] = bytes.try_into().ok()?;
+        Grid::from_bytes(bytes)
+    }
+
     pub(crate) fn count_distinct_tiles(self) -> usize {
         let mut grid = self.0;
         let mut bitset = 0u16;
@@ -382,11 +633,17 @@ impl Iterator for AiMoves {
 
 // Not much effort spent optimizing this, since it's going to be cached anyway
 fn move_row_left(row: Row) -> Row {
+    move_row_left_scored(row).0
+}
+
+// As `move_row_left`, but also returns the score awarded by any merges it performed.
+fn move_row_left_scored(row: Row) -> (Row, u32) {
     let from_row = row.unpack();
 
     let mut to_row = [0; 4];
     let mut last = 0;
     let mut last_index = 0;
+    let mut score = 0u32;
 
     for &tile in from_row.iter() {
         if tile == 0 {
@@ -399,6 +656,45 @@ fn move_row_left(row: Row) -> Row {
         }
 
         if tile == last {
+            let merged = last + 1;
+            to_row[last_index as usize] = merged;
+            score += 1u32 << merged;
+            last = 0;
+        } else {
+            to_row[last_index as usize] = last;
+            last = tile;
+        }
+
+        last_index += 1;
+    }
+
+    if last != 0 {
+        to_row[last_index as usize] = last;
+    }
+
+    (Row::pack(to_row).unwrap_or_default(), score)
+}
+
+// As `move_row_left`, but honoring a `Rules`' merge predicate and tile cap instead of the
+// classic, fixed rule. Not cache-backed, since the cache is baked in for the classic rules.
+fn move_row_left_with_rules(row: Row, rules: &Rules) -> Row {
+    let from_row = row.unpack();
+
+    let mut to_row = [0; 4];
+    let mut last = 0;
+    let mut last_index = 0;
+
+    for &tile in from_row.iter() {
+        if tile == 0 {
+            continue;
+        }
+
+        if last == 0 {
+            last = tile;
+            continue;
+        }
+
+        if tile == last && last < rules.max_tile_log && (rules.can_merge)(last) {
             to_row[last_index as usize] = last + 1;
             last = 0;
         } else {
@@ -428,6 +724,12 @@ fn lookup_up(row: Row) -> Column {
 fn lookup_down(row: Row) -> Column {
     unsafe { *CACHE_DOWN.get_unchecked(row.0 as usize) }
 }
+fn lookup_score_left(row: Row) -> u32 {
+    unsafe { *CACHE_SCORE_LEFT.get_unchecked(row.0 as usize) }
+}
+fn lookup_score_right(row: Row) -> u32 {
+    unsafe { *CACHE_SCORE_RIGHT.get_unchecked(row.0 as usize) }
+}
 
 lazy_static! {
     static ref CACHE_LEFT: Box<[Row]> = {
@@ -458,6 +760,63 @@ lazy_static! {
         }
         vec.into()
     };
+    static ref CACHE_SCORE_LEFT: Box<[u32]> = {
+        let mut vec = vec![0u32; u16::MAX as usize];
+        for (index, score) in vec.iter_mut().enumerate() {
+            *score = move_row_left_scored(Row(index as u16)).1;
+        }
+        vec.into()
+    };
+    static ref CACHE_SCORE_RIGHT: Box<[u32]> = {
+        let mut vec = vec![0u32; u16::MAX as usize];
+        for (index, score) in vec.iter_mut().enumerate() {
+            *score = move_row_left_scored(Row(index as u16).reverse()).1;
+        }
+        vec.into()
+    };
+}
+
+/// Wraps a `Grid` together with the running score accumulated from tile merges, mirroring the
+/// score counter of a real game of 2048.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Default, Debug)]
+pub struct GameState {
+    grid: Grid,
+    score: u32,
+}
+
+impl GameState {
+    /// Creates a new `GameState` wrapping the given `Grid`, with a score of `0`.
+    pub fn new(grid: Grid) -> GameState {
+        GameState { grid, score: 0 }
+    }
+
+    /// The wrapped `Grid`.
+    pub fn grid(self) -> Grid {
+        self.grid
+    }
+
+    /// The running score accumulated from tile merges so far.
+    pub fn score(self) -> u32 {
+        self.score
+    }
+
+    /// Makes a move, returning the resulting `GameState` with the score increased by whatever
+    /// that move merged. Returns `self` unchanged if the move is illegal.
+    pub fn make_move(self, mv: Move) -> GameState {
+        let (grid, gained) = self.grid.make_move_scored(mv);
+        GameState {
+            grid,
+            score: self.score + gained,
+        }
+    }
+
+    /// Adds a random tile to the wrapped `Grid`. See `Grid::add_random_tile`.
+    pub fn add_random_tile(self) -> GameState {
+        GameState {
+            grid: self.grid.add_random_tile(),
+            score: self.score,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -516,6 +875,113 @@ mod tests {
         }
     }
 
+    #[test]
+    fn can_roundtrip_bytes() {
+        let grid = Grid::from_human([
+            [0, 2, 4, 8],
+            [16, 32, 64, 128],
+            [256, 512, 1024, 2048],
+            [4096, 8192, 16384, 32768],
+        ])
+        .unwrap();
+
+        let roundtrip = Grid::from_bytes(grid.to_bytes()).unwrap();
+
+        assert_eq!(grid, roundtrip);
+    }
+
+    #[test]
+    fn can_roundtrip_base64() {
+        let grid = Grid::from_human([[0, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        let encoded = grid.to_base64();
+        let roundtrip = Grid::from_base64(&encoded).unwrap();
+
+        assert_eq!(grid, roundtrip);
+    }
+
+    #[test]
+    fn from_base64_rejects_garbage() {
+        assert!(Grid::from_base64("not valid base64!!").is_none());
+    }
+
+    #[test]
+    fn can_roundtrip_serde_compact() {
+        let grid = Grid::from_human([[0, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        let encoded = bincode::serialize(&grid).unwrap();
+        let decoded: Grid = bincode::deserialize(&encoded).unwrap();
+
+        assert_eq!(grid, decoded);
+    }
+
+    #[test]
+    fn can_roundtrip_serde_human_readable() {
+        let grid = Grid::from_human([[0, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        let encoded = serde_json::to_string(&grid).unwrap();
+        let decoded: Grid = serde_json::from_str(&encoded).unwrap();
+
+        assert_eq!(grid, decoded);
+    }
+
+    #[test]
+    fn serde_human_readable_rejects_invalid_tiles() {
+        let malformed = "[[0, 3, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]";
+
+        let result: Result<Grid, _> = serde_json::from_str(malformed);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn can_get_and_set_tiles() {
+        let grid = Grid::from_human([[0, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        assert_eq!(0, grid.tile_at(0, 0));
+        assert_eq!(2, grid.tile_at(0, 1));
+        assert_eq!(4, grid.tile_at(0, 2));
+
+        let updated = grid.with_tile(0, 0, 16).unwrap();
+        assert_eq!(16, updated.tile_at(0, 0));
+
+        assert!(grid.with_tile(0, 0, 3).is_none());
+    }
+
+    #[test]
+    fn can_iterate_empty_positions() {
+        let grid =
+            Grid::from_human([[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 4]]).unwrap();
+
+        let empty = grid.empty_positions().collect::<Vec<_>>();
+
+        assert_eq!(14, empty.len());
+        assert!(!empty.contains(&(0, 0)));
+        assert!(!empty.contains(&(3, 3)));
+        assert!(empty.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn can_iterate_rows_and_cols() {
+        let grid = Grid::from_human([
+            [0, 2, 4, 8],
+            [16, 32, 64, 128],
+            [256, 512, 1024, 2048],
+            [4096, 8192, 16384, 32768],
+        ])
+        .unwrap();
+
+        assert_eq!(vec![0, 2, 4, 8], grid.row_iter(0).collect::<Vec<_>>());
+        assert_eq!(
+            vec![0, 16, 256, 4096],
+            grid.col_iter(0).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn can_to_string() {
         let grid = Grid::from_human([
@@ -549,6 +1015,81 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn default_rules_match_classic_merge_behavior() {
+        let grid =
+            Grid::from_human([[2, 2, 4, 4], [0, 2, 2, 0], [0, 2, 2, 2], [2, 0, 0, 2]]).unwrap();
+
+        let classic = grid.make_move(Move::Left);
+        let via_rules = grid.make_move_with_rules(Move::Left, &Rules::default());
+
+        assert_eq!(classic, via_rules);
+    }
+
+    #[test]
+    fn tile_cap_suppresses_merges_above_it() {
+        let grid =
+            Grid::from_human([[2048, 2048, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+                .unwrap();
+        let rules = Rules {
+            max_tile_log: 11, // caps at 2048, so two 2048s may not merge into 4096
+            ..Rules::default()
+        };
+
+        let result = grid.make_move_with_rules(Move::Left, &rules);
+
+        assert_eq!(grid, result);
+    }
+
+    #[test]
+    fn custom_merge_predicate_can_forbid_merging() {
+        let grid =
+            Grid::from_human([[2, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]).unwrap();
+        let rules = Rules {
+            can_merge: |_| false,
+            ..Rules::default()
+        };
+
+        let result = grid.make_move_with_rules(Move::Left, &rules);
+
+        assert_eq!(grid, result);
+    }
+
+    #[test]
+    fn can_make_move_scored() {
+        let grid =
+            Grid::from_human([[2, 2, 4, 4], [0, 2, 2, 0], [0, 2, 2, 2], [2, 0, 0, 2]]).unwrap();
+
+        let (actual_grid, score) = grid.make_move_scored(Move::Left);
+
+        assert_eq!(grid.make_move(Move::Left), actual_grid);
+        assert_eq!(24, score);
+    }
+
+    #[test]
+    fn illegal_move_scored_scores_zero() {
+        let grid =
+            Grid::from_human([[2, 4, 8, 16], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]).unwrap();
+
+        let (actual_grid, score) = grid.make_move_scored(Move::Left);
+
+        assert_eq!(grid, actual_grid);
+        assert_eq!(0, score);
+    }
+
+    #[test]
+    fn game_state_accumulates_score_across_moves() {
+        let grid =
+            Grid::from_human([[2, 2, 4, 4], [0, 2, 2, 0], [0, 2, 2, 2], [2, 0, 0, 2]]).unwrap();
+
+        let state = GameState::new(grid).make_move(Move::Left);
+        assert_eq!(24, state.score());
+
+        // Moving left again is illegal (nothing changes), so the score doesn't increase.
+        let state = state.make_move(Move::Left);
+        assert_eq!(24, state.score());
+    }
+
     #[test]
     fn can_make_move_right() {
         let grid =
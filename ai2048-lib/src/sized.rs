@@ -0,0 +1,441 @@
+//! A const-generic board, for board sizes other than the classic 4×4.
+//!
+//! [`crate::game_logic::Grid`] hardwires a 4×4 board into a single `u64` and ships hand-tuned,
+//! fully cached row-move lookup tables for it; that representation stays the default, fast path.
+//! This module generalizes the same bitboard idea to arbitrary `N`×`N` boards via a const
+//! generic, at the cost of computing row moves directly instead of through a cache (a `u16::MAX`
+//! or `u32::MAX`-sized cache per `N` isn't something we want to pay for on every board size) --
+//! except at `N = 4`, where [`BoardOps`] routes straight through `Grid`'s cached implementation
+//! instead of recomputing anything, since `Board<4>` packs its nibbles in exactly the bit layout
+//! `Grid` does (see `index`).
+//!
+//! The backing integer widens with `N`: 16 nibbles (4×4) fit in a `u64`, but 25 nibbles (5×5)
+//! need a `u128`. [`BoardRepr`] picks the right one per `N` so callers never have to.
+
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{BitOr, Shl, Shr};
+
+use crate::game_logic::{Grid, Move};
+
+/// The integer type used to pack an `N`×`N` board's nibbles, one nibble (4 bits) per tile's
+/// log2 exponent. Implemented for `u64` (up to 16 nibbles) and `u128` (up to 32 nibbles).
+pub trait Nibbles:
+    Copy + Default + Eq + Hash + BitOr<Output = Self> + Shl<u32, Output = Self> + Shr<u32, Output = Self>
+{
+    /// Reads the nibble at `index` (`0` is the least significant nibble).
+    fn nibble(self, index: usize) -> u8;
+    /// Returns a copy of `self` with the nibble at `index` set to `value`.
+    fn with_nibble(self, index: usize, value: u8) -> Self;
+    /// Counts how many of the `total` low nibbles are zero.
+    fn count_empty_nibbles(self, total: usize) -> usize;
+}
+
+macro_rules! impl_nibbles {
+    ($t:ty) => {
+        impl Nibbles for $t {
+            fn nibble(self, index: usize) -> u8 {
+                ((self >> (index as u32 * 4)) & 0xF) as u8
+            }
+
+            fn with_nibble(self, index: usize, value: u8) -> Self {
+                let shift = index as u32 * 4;
+                (self & !(0xF << shift)) | (<$t>::from(value) << shift)
+            }
+
+            fn count_empty_nibbles(self, total: usize) -> usize {
+                (0..total).filter(|&i| self.nibble(i) == 0).count()
+            }
+        }
+    };
+}
+
+impl_nibbles!(u64);
+impl_nibbles!(u128);
+
+/// Selects the narrowest [`Nibbles`] integer that can hold an `N`×`N` board. Implemented for
+/// the board sizes this crate supports; add an impl here to support a new `N`.
+pub trait BoardRepr<const N: usize> {
+    /// The backing integer for a board of this size.
+    type Repr: Nibbles;
+}
+
+/// Marker type used only to hang [`BoardRepr`] impls off of a given `N`.
+pub struct Dim<const N: usize>;
+
+impl BoardRepr<3> for Dim<3> {
+    type Repr = u64;
+}
+impl BoardRepr<4> for Dim<4> {
+    type Repr = u64;
+}
+impl BoardRepr<5> for Dim<5> {
+    type Repr = u128;
+}
+
+/// Computes the two operations that are worth specializing per `N`: making a move, and counting
+/// empty tiles. The general `N` = 3 / `N` = 5 cases compute these directly on the packed nibbles,
+/// uncached (see the module docs). `N` = 4 instead delegates straight to `game_logic::Grid`'s
+/// hand-cached, bit-tricked implementation: no blanket impl exists across `N`, so this is one
+/// explicit impl per supported size, same as `BoardRepr`.
+pub trait BoardOps<const N: usize>: BoardRepr<N> {
+    fn make_move(bits: Self::Repr, mv: Move) -> Self::Repr;
+    fn count_empty(bits: Self::Repr) -> usize;
+}
+
+impl BoardOps<3> for Dim<3> {
+    fn make_move(bits: u64, mv: Move) -> u64 {
+        scalar_make_move::<u64, 3>(bits, mv)
+    }
+
+    fn count_empty(bits: u64) -> usize {
+        bits.count_empty_nibbles(3 * 3)
+    }
+}
+
+impl BoardOps<5> for Dim<5> {
+    fn make_move(bits: u128, mv: Move) -> u128 {
+        scalar_make_move::<u128, 5>(bits, mv)
+    }
+
+    fn count_empty(bits: u128) -> usize {
+        bits.count_empty_nibbles(5 * 5)
+    }
+}
+
+impl BoardOps<4> for Dim<4> {
+    fn make_move(bits: u64, mv: Move) -> u64 {
+        Grid::from_bits(bits).make_move(mv).bits()
+    }
+
+    fn count_empty(bits: u64) -> usize {
+        Grid::from_bits(bits).count_empty()
+    }
+}
+
+/// A board of `N`×`N` tiles, generalizing [`crate::game_logic::Grid`] to sizes other than 4×4.
+#[derive(Copy, Clone)]
+pub struct Board<const N: usize>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    bits: <Dim<N> as BoardRepr<N>>::Repr,
+}
+
+impl<const N: usize> PartialEq for Board<N>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+impl<const N: usize> Eq for Board<N> where Dim<N>: BoardRepr<N> {}
+
+impl<const N: usize> Hash for Board<N>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+
+impl<const N: usize> Default for Board<N>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    fn default() -> Self {
+        Board {
+            bits: Default::default(),
+        }
+    }
+}
+
+impl<const N: usize> fmt::Debug for Board<N>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.unpack_log().iter() {
+            write!(f, "{:?} ", row)?;
+        }
+        Ok(())
+    }
+}
+
+fn to_log(n: u32) -> Option<u8> {
+    let log = if n == 0 { 0f32 } else { (n as f32).log2() };
+    let rounded = log.round();
+    if (rounded - log).abs() < 1e-10 {
+        Some(rounded as u8)
+    } else {
+        None
+    }
+}
+
+// Nibble index (`0` is the least significant nibble) for `(row, col)`, chosen so that at `N = 4`
+// it reproduces `game_logic::Grid`'s own layout exactly: row 0 in the high nibbles, column 0 the
+// most significant nibble of its row. This is what lets `BoardOps<4>` hand `Board<4>`'s raw bits
+// straight to `Grid` (and back) with no reinterpretation.
+fn index<const N: usize>(row: usize, col: usize) -> usize {
+    (N - 1 - row) * N + (N - 1 - col)
+}
+
+impl<const N: usize> Board<N>
+where
+    Dim<N>: BoardRepr<N>,
+{
+    /// Creates a new `Board` from an array of human-looking tile values. Returns `None` if any
+    /// tile isn't zero or a power of two, or doesn't fit in a nibble (i.e. is larger than
+    /// `2^15`).
+    pub fn from_human(grid: [[u32; N]; N]) -> Option<Board<N>> {
+        let mut log = [[0u8; N]; N];
+        for row in 0..N {
+            for col in 0..N {
+                log[row][col] = to_log(grid[row][col])?;
+            }
+        }
+        Self::from_log(log)
+    }
+
+    /// Unpacks a human-readable representation of this `Board`.
+    pub fn unpack_human(self) -> [[u32; N]; N] {
+        let mut result = [[0u32; N]; N];
+        for (row, tiles) in self.unpack_log().iter().enumerate() {
+            for (col, &tile) in tiles.iter().enumerate() {
+                result[row][col] = if tile == 0 { 0 } else { 1 << tile };
+            }
+        }
+        result
+    }
+
+    fn from_log(grid: [[u8; N]; N]) -> Option<Board<N>> {
+        scalar_pack_log::<<Dim<N> as BoardRepr<N>>::Repr, N>(grid).map(|bits| Board { bits })
+    }
+
+    fn unpack_log(self) -> [[u8; N]; N] {
+        scalar_unpack_log::<_, N>(self.bits)
+    }
+}
+
+impl<const N: usize> Board<N>
+where
+    Dim<N>: BoardOps<N>,
+{
+    /// Counts the number of empty tiles on the board.
+    pub fn count_empty(self) -> usize {
+        <Dim<N> as BoardOps<N>>::count_empty(self.bits)
+    }
+
+    /// Returns the `Board` that would result from making a certain `Move` in the current state.
+    pub fn make_move(self, mv: Move) -> Board<N> {
+        Board {
+            bits: <Dim<N> as BoardOps<N>>::make_move(self.bits, mv),
+        }
+    }
+
+    /// Returns `(Move, Board)` pairs for every legal move from this position.
+    pub fn player_moves(self) -> impl Iterator<Item = (Move, Board<N>)> {
+        crate::game_logic::MOVES.iter().filter_map(move |&m| {
+            let new_board = self.make_move(m);
+            if new_board == self {
+                None
+            } else {
+                Some((m, new_board))
+            }
+        })
+    }
+
+    /// Whether no legal move remains.
+    pub fn game_over(self) -> bool {
+        self.player_moves().next().is_none()
+    }
+}
+
+fn scalar_unpack_log<R: Nibbles, const N: usize>(bits: R) -> [[u8; N]; N] {
+    let mut result = [[0u8; N]; N];
+    for row in 0..N {
+        for col in 0..N {
+            result[row][col] = bits.nibble(index::<N>(row, col));
+        }
+    }
+    result
+}
+
+fn scalar_pack_log<R: Nibbles, const N: usize>(grid: [[u8; N]; N]) -> Option<R> {
+    let mut bits = R::default();
+    for row in 0..N {
+        for col in 0..N {
+            if grid[row][col] > 0b1111 {
+                return None;
+            }
+            bits = bits.with_nibble(index::<N>(row, col), grid[row][col]);
+        }
+    }
+    Some(bits)
+}
+
+fn scalar_transpose<R: Nibbles, const N: usize>(bits: R) -> R {
+    let mut grid = scalar_unpack_log::<R, N>(bits);
+    for row in 0..N {
+        for col in (row + 1)..N {
+            let tmp = grid[row][col];
+            grid[row][col] = grid[col][row];
+            grid[col][row] = tmp;
+        }
+    }
+    scalar_pack_log::<R, N>(grid).expect("transposing a valid board can't overflow a nibble")
+}
+
+fn scalar_move_rows<R: Nibbles, const N: usize>(bits: R, f: impl Fn([u8; N]) -> [u8; N]) -> R {
+    let mut grid = scalar_unpack_log::<R, N>(bits);
+    for row in grid.iter_mut() {
+        *row = f(*row);
+    }
+    scalar_pack_log::<R, N>(grid).expect("moving a valid board can't overflow a nibble")
+}
+
+// Direct, uncached row-move computation over the packed nibbles (as opposed to `game_logic`'s
+// lookup-table-backed `move_row_left`), since a full cache would need to be sized per `N`. Used
+// for every `N` but 4, which instead routes through `game_logic::Grid` (see `BoardOps`).
+fn scalar_make_move<R: Nibbles, const N: usize>(bits: R, mv: Move) -> R {
+    match mv {
+        Move::Left => scalar_move_rows::<R, N>(bits, move_row_left),
+        Move::Right => scalar_move_rows::<R, N>(bits, move_row_right),
+        Move::Up => scalar_transpose::<R, N>(scalar_move_rows::<R, N>(
+            scalar_transpose::<R, N>(bits),
+            move_row_left,
+        )),
+        Move::Down => scalar_transpose::<R, N>(scalar_move_rows::<R, N>(
+            scalar_transpose::<R, N>(bits),
+            move_row_right,
+        )),
+    }
+}
+
+fn move_row_left<const N: usize>(row: [u8; N]) -> [u8; N] {
+    let mut to_row = [0u8; N];
+    let mut last = 0u8;
+    let mut last_index = 0usize;
+
+    for &tile in row.iter() {
+        if tile == 0 {
+            continue;
+        }
+
+        if last == 0 {
+            last = tile;
+            continue;
+        }
+
+        if tile == last {
+            to_row[last_index] = last + 1;
+            last = 0;
+        } else {
+            to_row[last_index] = last;
+            last = tile;
+        }
+
+        last_index += 1;
+    }
+
+    if last != 0 {
+        to_row[last_index] = last;
+    }
+
+    to_row
+}
+
+fn move_row_right<const N: usize>(row: [u8; N]) -> [u8; N] {
+    let mut reversed = row;
+    reversed.reverse();
+    let mut moved = move_row_left(reversed);
+    moved.reverse();
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_roundtrip_3x3_board() {
+        let human = [[0, 2, 4], [8, 16, 32], [64, 128, 256]];
+
+        let board: Board<3> = Board::from_human(human).unwrap();
+
+        assert_eq!(human, board.unpack_human());
+    }
+
+    #[test]
+    fn can_roundtrip_5x5_board() {
+        let human = [
+            [0, 2, 4, 8, 16],
+            [32, 64, 128, 256, 512],
+            [1024, 2048, 4096, 8192, 16384],
+            [32768, 0, 2, 4, 8],
+            [16, 32, 64, 128, 256],
+        ];
+
+        let board: Board<5> = Board::from_human(human).unwrap();
+
+        assert_eq!(human, board.unpack_human());
+    }
+
+    #[test]
+    fn can_make_move_left_3x3() {
+        let board: Board<3> = Board::from_human([[2, 2, 4], [0, 2, 2], [2, 0, 2]]).unwrap();
+        let expected: Board<3> = Board::from_human([[4, 4, 0], [4, 0, 0], [4, 0, 0]]).unwrap();
+
+        assert_eq!(expected, board.make_move(Move::Left));
+    }
+
+    #[test]
+    fn can_count_empty() {
+        let board: Board<3> = Board::from_human([[0, 2, 4], [0, 0, 32], [0, 0, 0]]).unwrap();
+
+        assert_eq!(6, board.count_empty());
+    }
+
+    #[test]
+    fn can_detect_game_over() {
+        let terminal: Board<3> = Board::from_human([[2, 4, 2], [4, 2, 4], [2, 4, 2]]).unwrap();
+        let normal: Board<3> = Board::from_human([[0, 4, 2], [4, 2, 4], [2, 4, 2]]).unwrap();
+
+        assert!(terminal.game_over());
+        assert!(!normal.game_over());
+    }
+
+    #[test]
+    fn board4_packs_bits_identically_to_grid() {
+        let human = [
+            [0, 2, 4, 8],
+            [16, 32, 64, 128],
+            [256, 512, 1024, 2048],
+            [4096, 8192, 16384, 32768],
+        ];
+
+        let board: Board<4> = Board::from_human(human).unwrap();
+        let grid = Grid::from_human(human).unwrap();
+
+        assert_eq!(grid.bits(), board.bits);
+    }
+
+    #[test]
+    fn board4_make_move_and_count_empty_match_grid() {
+        let human = [[2, 2, 4, 4], [0, 2, 2, 0], [0, 2, 2, 2], [2, 0, 0, 2]];
+
+        let board: Board<4> = Board::from_human(human).unwrap();
+        let grid = Grid::from_human(human).unwrap();
+
+        assert_eq!(grid.count_empty(), board.count_empty());
+
+        for &mv in &crate::game_logic::MOVES {
+            assert_eq!(
+                grid.make_move(mv).unpack_human(),
+                board.make_move(mv).unpack_human()
+            );
+        }
+    }
+}
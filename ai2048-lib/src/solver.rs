@@ -0,0 +1,137 @@
+//! Deterministic pathfinding over the move graph, ignoring random tile spawns: answers
+//! "what's the shortest sequence of moves to reach a given tile?". This is a puzzle-style
+//! planner, distinct from the stochastic expectimax AI in `ai`.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::game_logic::{Grid, Move};
+
+impl Grid {
+    /// Finds the shortest sequence of moves (ignoring random tile spawns) that reaches a board
+    /// containing a tile whose log2 exponent is at least `target_log`. Returns `Some(vec![])`
+    /// if the current board already does, and `None` if no reachable board does.
+    pub fn shortest_moves_to(self, target_log: u8) -> Option<Vec<Move>> {
+        if max_tile_log(self) >= target_log {
+            return Some(Vec::new());
+        }
+
+        let mut best_cost = HashMap::new();
+        let mut predecessor = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        best_cost.insert(self, 0usize);
+        queue.push(QueueEntry {
+            priority: heuristic(self, target_log),
+            cost: 0,
+            grid: self,
+        });
+
+        while let Some(QueueEntry { cost, grid, .. }) = queue.pop() {
+            if cost > *best_cost.get(&grid).unwrap_or(&usize::max_value()) {
+                // A better path to this board was already found; this entry is stale.
+                continue;
+            }
+
+            if max_tile_log(grid) >= target_log {
+                return Some(reconstruct_path(grid, &predecessor));
+            }
+
+            for (mv, next) in grid.player_moves() {
+                let next_cost = cost + 1;
+                if next_cost < *best_cost.get(&next).unwrap_or(&usize::max_value()) {
+                    best_cost.insert(next, next_cost);
+                    predecessor.insert(next, (mv, grid));
+                    queue.push(QueueEntry {
+                        priority: next_cost + heuristic(next, target_log),
+                        cost: next_cost,
+                        grid: next,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn reconstruct_path(mut grid: Grid, predecessor: &HashMap<Grid, (Move, Grid)>) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(&(mv, prev)) = predecessor.get(&grid) {
+        moves.push(mv);
+        grid = prev;
+    }
+    moves.reverse();
+    moves
+}
+
+fn max_tile_log(grid: Grid) -> u8 {
+    let max_value = (0..4).flat_map(|row| grid.row_iter(row)).max().unwrap_or(0);
+    if max_value == 0 {
+        0
+    } else {
+        (31 - max_value.leading_zeros()) as u8
+    }
+}
+
+// An admissible heuristic: at least `target_log - max_tile_log(grid)` more moves are needed,
+// since no single move can raise the current maximum tile's exponent by more than one.
+fn heuristic(grid: Grid, target_log: u8) -> usize {
+    target_log.saturating_sub(max_tile_log(grid)) as usize
+}
+
+struct QueueEntry {
+    priority: usize,
+    cost: usize,
+    grid: Grid,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap; reverse so the lowest priority (cost + heuristic) wins.
+        other.priority.cmp(&self.priority)
+    }
+}
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn already_at_target_returns_empty_path() {
+        let grid = Grid::from_human([[4, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        assert_eq!(Some(Vec::new()), grid.shortest_moves_to(2));
+    }
+
+    #[test]
+    fn finds_a_one_move_merge() {
+        let grid = Grid::from_human([[2, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+            .unwrap();
+
+        let moves = grid.shortest_moves_to(2).unwrap();
+
+        assert_eq!(1, moves.len());
+        assert!(max_tile_log(grid.make_move(moves[0])) >= 2);
+    }
+
+    #[test]
+    fn unreachable_target_returns_none() {
+        let grid = Grid::default();
+
+        assert_eq!(None, grid.shortest_moves_to(1));
+    }
+}
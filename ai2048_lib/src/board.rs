@@ -0,0 +1,452 @@
+//! 2048 game logic: `Board` encodes all the rules of the game, generating new states given a
+//! move a player makes, or all possible states following the computer spawning a random tile.
+//! A board is sixteen tiles, each packed into one nibble (4 bits) of a `u64`: the nibble holds
+//! the tile's log2 exponent (`0` for an empty tile, `1` for a `2`, `2` for a `4`, and so on).
+
+use lazy_static::lazy_static;
+use rand::{self, Rng};
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use std::{fmt, u16};
+
+/// Represents a move.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[repr(u8)]
+pub enum Move {
+    /// Move left.
+    Left = 0,
+    /// Move right.
+    Right = 1,
+    /// Move up.
+    Up = 2,
+    /// Move down.
+    Down = 3,
+}
+
+/// All possible moves.
+pub const MOVES: [Move; 4] = [Move::Left, Move::Right, Move::Up, Move::Down];
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::Down => "Down".fmt(f),
+            Move::Left => "Left".fmt(f),
+            Move::Right => "Right".fmt(f),
+            Move::Up => "Up".fmt(f),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Default)]
+struct Row(u16);
+
+impl Row {
+    fn pack(row: [u8; 4]) -> Option<Row> {
+        let mut result = 0;
+        for &tile in &row {
+            if tile > 0b1111 {
+                return None;
+            }
+            result <<= 4;
+            result += u16::from(tile);
+        }
+        Some(Row(result))
+    }
+
+    fn unpack(self) -> [u8; 4] {
+        let row = self.0;
+        let tile0 = ((row & 0b1111_0000_0000_0000) >> 12) as u8;
+        let tile1 = ((row & 0b0000_1111_0000_0000) >> 8) as u8;
+        let tile2 = ((row & 0b0000_0000_1111_0000) >> 4) as u8;
+        let tile3 = (row & 0b0000_0000_0000_1111) as u8;
+        [tile0, tile1, tile2, tile3]
+    }
+
+    fn reverse(self) -> Self {
+        Row((self.0 >> 12)
+            | ((self.0 >> 4) & 0b0000_0000_1111_0000)
+            | ((self.0 << 4) & 0b0000_1111_0000_0000)
+            | (self.0 << 12))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Column(u64);
+
+impl Column {
+    fn from_row(row: Row) -> Self {
+        const COLUMN_MASK: u64 = 0x000F_000F_000F_000F;
+        let col = (u64::from(row.0)
+            | u64::from(row.0) << 12
+            | u64::from(row.0) << 24
+            | u64::from(row.0) << 36)
+            & COLUMN_MASK;
+        Column(col)
+    }
+}
+
+/// `Board`, in general, encodes all the rules of the game: it can generate new states given a
+/// move a player makes, or all possible states following the computer spawning a random tile.
+///
+/// Derives `rkyv`'s `Archive` (with `check_bytes`) so it can be used as a key in a
+/// `persistence::Entry`, which is what `SearchTree::save`/`ArchivedTable` round-trip to disk.
+#[derive(Eq, PartialEq, Hash, Copy, Clone, Default, Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+pub struct Board(u64);
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.unpack_human().iter() {
+            for &tile in row {
+                write!(f, "{number:>width$}", number = tile, width = 6)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows().iter() {
+            write!(f, "{:?} ", row.unpack())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn to_log(n: u32) -> Option<u8> {
+    let log = match n {
+        0 => 0f32,
+        _ => (n as f32).log2(),
+    };
+
+    let rounded = log.round();
+    if (rounded - log).abs() < 1e-10 {
+        Some(rounded as u8)
+    } else {
+        None
+    }
+}
+
+impl Board {
+    /// Creates a new `Board` from an array of human-looking numbers. If a tile fails to be a
+    /// power of 2, or is larger than 32768, returns `None`.
+    pub fn from_u32(grid: [[u32; 4]; 4]) -> Option<Board> {
+        let mut rows = [Row::default(); 4];
+        for (x, &row) in grid.iter().enumerate() {
+            let mut new_row = [0u8; 4];
+            for (y, &tile) in row.iter().enumerate() {
+                let log = to_log(tile)?;
+                new_row[y] = log;
+            }
+
+            rows[x] = Row::pack(new_row)?;
+        }
+        Some(Board::from_rows(rows))
+    }
+
+    /// Unpacks a human-readable representation from `Board`'s internal representation.
+    pub fn unpack_human(self) -> [[u32; 4]; 4] {
+        let mut result = [[0; 4]; 4];
+        for (x, row) in self.rows().iter().enumerate() {
+            for (y, &tile) in row.unpack().iter().enumerate() {
+                result[x][y] = match tile {
+                    0 => 0,
+                    _ => 1 << tile,
+                };
+            }
+        }
+        result
+    }
+
+    fn rows(self) -> [Row; 4] {
+        let row1 = Row(((self.0 & 0xFFFF_0000_0000_0000) >> 48) as u16);
+        let row2 = Row(((self.0 & 0x0000_FFFF_0000_0000) >> 32) as u16);
+        let row3 = Row(((self.0 & 0x0000_0000_FFFF_0000) >> 16) as u16);
+        let row4 = Row((self.0 & 0x0000_0000_0000_FFFF) as u16);
+        [row1, row2, row3, row4]
+    }
+
+    fn from_rows(rows: [Row; 4]) -> Self {
+        let mut board = Board::default();
+        board.0 |= u64::from(rows[0].0) << 48;
+        board.0 |= u64::from(rows[1].0) << 32;
+        board.0 |= u64::from(rows[2].0) << 16;
+        board.0 |= u64::from(rows[3].0);
+        board
+    }
+
+    fn from_columns(columns: [Column; 4]) -> Self {
+        let mut board = Board::default();
+        board.0 |= columns[0].0 << 12;
+        board.0 |= columns[1].0 << 8;
+        board.0 |= columns[2].0 << 4;
+        board.0 |= columns[3].0;
+        board
+    }
+
+    /// The raw packed bitboard: sixteen nibbles, one per tile's log2 exponent. Used by
+    /// transposition table backends (see `search_tree::cache`) that index directly on a
+    /// board's bit pattern instead of hashing it.
+    pub(crate) fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn game_over(self) -> bool {
+        MOVES.iter().all(|&m| self.make_move(m) == self)
+    }
+
+    /// Creates a new `Board` with a random tile (90% of times a `2`, 10% of times a `4`) added
+    /// to a random empty tile on the board.
+    pub fn add_random_tile(self) -> Board {
+        let mut rng = rand::thread_rng();
+
+        let mut grid = self.rows().map(Row::unpack);
+        let empty_tile_count = grid.iter().flatten().filter(|v| **v == 0).count();
+        let position = rng.gen_range(0, empty_tile_count);
+
+        let value = grid
+            .iter_mut()
+            .flatten()
+            .filter(|v| **v == 0)
+            .nth(position)
+            .unwrap();
+
+        *value = if rng.gen_bool(0.1) { 2 } else { 1 };
+
+        let rows = [
+            Row::pack(grid[0]).unwrap(),
+            Row::pack(grid[1]).unwrap(),
+            Row::pack(grid[2]).unwrap(),
+            Row::pack(grid[3]).unwrap(),
+        ];
+        Board::from_rows(rows)
+    }
+
+    pub(crate) fn ai_moves_with2(self) -> impl Iterator<Item = Board> {
+        AiMoves::new(self, 1)
+    }
+
+    pub(crate) fn ai_moves_with4(self) -> impl Iterator<Item = Board> {
+        AiMoves::new(self, 2)
+    }
+
+    fn transpose(self) -> Board {
+        let x = self.0;
+        let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+        let a2 = x & 0x0000_F0F0_0000_F0F0;
+        let a3 = x & 0x0F0F_0000_0F0F_0000;
+        let a = a1 | (a2 << 12) | (a3 >> 12);
+        let b1 = a & 0xFF00_FF00_00FF_00FF;
+        let b2 = a & 0x00FF_00FF_0000_0000;
+        let b3 = a & 0x0000_0000_FF00_FF00;
+        let ret = b1 | (b2 >> 24) | (b3 << 24);
+        Board(ret)
+    }
+
+    /// Returns a `Board` that would result from making a certain `Move` in the current state.
+    pub fn make_move(self, mv: Move) -> Board {
+        match mv {
+            Move::Left => self.move_left(),
+            Move::Right => self.move_right(),
+            Move::Up => self.move_up(),
+            Move::Down => self.move_down(),
+        }
+    }
+
+    fn move_left(self) -> Board {
+        let rows = self.rows();
+        Board::from_rows([
+            lookup_left(rows[0]),
+            lookup_left(rows[1]),
+            lookup_left(rows[2]),
+            lookup_left(rows[3]),
+        ])
+    }
+
+    fn move_right(self) -> Board {
+        let rows = self.rows();
+        Board::from_rows([
+            lookup_right(rows[0]),
+            lookup_right(rows[1]),
+            lookup_right(rows[2]),
+            lookup_right(rows[3]),
+        ])
+    }
+
+    fn move_up(self) -> Board {
+        let rows = self.transpose().rows();
+        Board::from_columns([
+            lookup_up(rows[0]),
+            lookup_up(rows[1]),
+            lookup_up(rows[2]),
+            lookup_up(rows[3]),
+        ])
+    }
+
+    fn move_down(self) -> Board {
+        let rows = self.transpose().rows();
+        Board::from_columns([
+            lookup_down(rows[0]),
+            lookup_down(rows[1]),
+            lookup_down(rows[2]),
+            lookup_down(rows[3]),
+        ])
+    }
+}
+
+struct AiMoves {
+    board: Board,
+    index: i8,
+    val: u8,
+}
+
+impl AiMoves {
+    fn new(board: Board, new_value: u8) -> AiMoves {
+        AiMoves {
+            board,
+            index: 16,
+            val: new_value,
+        }
+    }
+}
+
+impl Iterator for AiMoves {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        loop {
+            self.index -= 1;
+            if self.index < 0 {
+                return None;
+            }
+            let mask = 0b1111u64 << (self.index * 4);
+            if (self.board.0 & mask) == 0 {
+                let board = Board(self.board.0 | u64::from(self.val) << (self.index * 4));
+                return Some(board);
+            }
+        }
+    }
+}
+
+fn move_row_left(row: Row) -> Row {
+    let from_row = row.unpack();
+
+    let mut to_row = [0; 4];
+    let mut last = 0;
+    let mut last_index = 0;
+
+    for &tile in from_row.iter() {
+        if tile == 0 {
+            continue;
+        }
+
+        if last == 0 {
+            last = tile;
+            continue;
+        }
+
+        if tile == last {
+            to_row[last_index as usize] = last + 1;
+            last = 0;
+        } else {
+            to_row[last_index as usize] = last;
+            last = tile;
+        }
+
+        last_index += 1;
+    }
+
+    if last != 0 {
+        to_row[last_index as usize] = last;
+    }
+
+    Row::pack(to_row).unwrap_or_default()
+}
+
+fn lookup_left(row: Row) -> Row {
+    unsafe { *CACHE_LEFT.get_unchecked(row.0 as usize) }
+}
+fn lookup_right(row: Row) -> Row {
+    unsafe { *CACHE_RIGHT.get_unchecked(row.0 as usize) }
+}
+fn lookup_up(row: Row) -> Column {
+    unsafe { *CACHE_UP.get_unchecked(row.0 as usize) }
+}
+fn lookup_down(row: Row) -> Column {
+    unsafe { *CACHE_DOWN.get_unchecked(row.0 as usize) }
+}
+
+lazy_static! {
+    static ref CACHE_LEFT: Box<[Row]> = {
+        let mut vec = vec![Row::default(); u16::MAX as usize];
+        for (index, row) in vec.iter_mut().enumerate() {
+            *row = move_row_left(Row(index as u16));
+        }
+        vec.into()
+    };
+    static ref CACHE_RIGHT: Box<[Row]> = {
+        let mut vec = vec![Row::default(); u16::MAX as usize];
+        for (index, row) in vec.iter_mut().enumerate() {
+            *row = move_row_left(Row(index as u16).reverse()).reverse();
+        }
+        vec.into()
+    };
+    static ref CACHE_UP: Box<[Column]> = {
+        let mut vec = vec![Column::default(); u16::MAX as usize];
+        for (index, col) in vec.iter_mut().enumerate() {
+            *col = Column::from_row(CACHE_LEFT[index]);
+        }
+        vec.into()
+    };
+    static ref CACHE_DOWN: Box<[Column]> = {
+        let mut vec = vec![Column::default(); u16::MAX as usize];
+        for (index, col) in vec.iter_mut().enumerate() {
+            *col = Column::from_row(CACHE_RIGHT[index]);
+        }
+        vec.into()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_create_board_from_u32() {
+        let human = [
+            [0, 2, 4, 8],
+            [16, 32, 64, 128],
+            [256, 512, 1024, 2048],
+            [4096, 8192, 16384, 32768],
+        ];
+
+        let board = Board::from_u32(human).unwrap();
+
+        assert_eq!(human, board.unpack_human());
+    }
+
+    #[test]
+    fn can_make_move_left() {
+        let board =
+            Board::from_u32([[2, 2, 4, 4], [0, 2, 2, 0], [0, 2, 2, 2], [2, 0, 0, 2]]).unwrap();
+        let expected =
+            Board::from_u32([[4, 8, 0, 0], [4, 0, 0, 0], [4, 2, 0, 0], [4, 0, 0, 0]]).unwrap();
+
+        assert_eq!(expected, board.make_move(Move::Left));
+    }
+
+    #[test]
+    fn can_detect_terminal_state() {
+        let terminal =
+            Board::from_u32([[4, 16, 8, 4], [8, 128, 32, 2], [2, 32, 16, 8], [4, 2, 4, 2]])
+                .unwrap();
+        let normal =
+            Board::from_u32([[0, 8, 8, 8], [8, 8, 0, 8], [8, 8, 8, 0], [8, 0, 8, 8]]).unwrap();
+
+        assert!(terminal.game_over());
+        assert!(!normal.game_over());
+    }
+}
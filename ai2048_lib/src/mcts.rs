@@ -0,0 +1,239 @@
+//! A Monte Carlo Tree Search engine, as an alternative to exhaustively expanding the full
+//! `search_tree`. Rather than visiting every reachable board, it grows only the branches that
+//! look promising, guided by random rollouts.
+//!
+//! This reuses the existing `SearchTree`/`PlayerNode`/`ComputerNode` graph and transposition
+//! `Cache` as-is: each `PlayerNode`'s generic `data` slot holds this module's [`NodeStats`]
+//! (visit count and accumulated rollout value), and a `ComputerNode`'s own statistics are simply
+//! the sum of its children's, since chance nodes don't carry a `data` slot of their own.
+
+use rand::Rng;
+
+use crate::board::{self, Board, Move};
+use crate::search_tree::{ComputerNode, PlayerNode, PlayerNodeChildren, SearchTree};
+
+/// Per-node Monte Carlo statistics: how many rollouts have passed through this node, and the
+/// sum of the values those rollouts produced.
+#[derive(Copy, Clone, Default)]
+pub struct NodeStats {
+    visits: u32,
+    total_value: f64,
+}
+
+// A game-over `PlayerNode` has no children to expand or simulate from, so it short-circuits to
+// this fixed value rather than running a (trivial, empty) rollout.
+const TERMINAL_VALUE: f64 = 0.0;
+
+/// Runs `iterations` rounds of MCTS from `tree`'s root, using `exploration` as the UCT
+/// exploration constant `C` and `heuristic` to score the terminal board of each rollout.
+/// Returns the root move with the most visits, or `None` if the root is already game over.
+pub fn search(
+    tree: &SearchTree<NodeStats>,
+    iterations: usize,
+    exploration: f64,
+    heuristic: impl Fn(Board) -> f64,
+    rng: &mut impl Rng,
+) -> Option<Move> {
+    let root = tree.root();
+
+    if root.children().is_empty() {
+        return None;
+    }
+
+    for _ in 0..iterations {
+        run_iteration(root, exploration, &heuristic, rng);
+    }
+
+    root.children()
+        .iter()
+        .max_by_key(|(_, computer)| computer_node_stats(*computer).visits)
+        .map(|(mv, _)| mv)
+}
+
+fn run_iteration(
+    root: PlayerNode<'_, NodeStats>,
+    exploration: f64,
+    heuristic: &impl Fn(Board) -> f64,
+    rng: &mut impl Rng,
+) {
+    let mut path = vec![root];
+    let mut node = root;
+
+    let value = loop {
+        // Forces this node's children into existence the first time it's visited.
+        let children = node.children();
+        if children.is_empty() {
+            break TERMINAL_VALUE;
+        }
+
+        let parent_visits = node.data.get().visits;
+        let chosen = select_child(children, parent_visits, exploration);
+
+        // Forces the chance node's children, then samples one with real spawn odds: 90% a `2`,
+        // 10% a `4`.
+        let spawn_children = chosen.children();
+        let next = if rng.gen_bool(0.9) {
+            sample(spawn_children.with2(), rng)
+        } else {
+            sample(spawn_children.with4(), rng)
+        };
+
+        path.push(next);
+
+        if next.data.get().visits == 0 {
+            // `next` was just expanded and has never been visited: simulate from here rather
+            // than continuing to select deeper into a subtree we know nothing about yet.
+            break simulate(next.board(), heuristic, rng);
+        }
+
+        node = next;
+    };
+
+    for player_node in &path {
+        let stats = player_node.data.get();
+        player_node.data.set(NodeStats {
+            visits: stats.visits + 1,
+            total_value: stats.total_value + value,
+        });
+    }
+}
+
+fn select_child<'a>(
+    children: PlayerNodeChildren<'a, NodeStats>,
+    parent_visits: u32,
+    exploration: f64,
+) -> ComputerNode<'a, NodeStats> {
+    children
+        .values()
+        .max_by(|a, b| {
+            let uct_a = uct(computer_node_stats(*a), parent_visits, exploration);
+            let uct_b = uct(computer_node_stats(*b), parent_visits, exploration);
+            uct_a.partial_cmp(&uct_b).unwrap()
+        })
+        .expect("children is non-empty, checked by the caller")
+}
+
+fn computer_node_stats(computer: ComputerNode<'_, NodeStats>) -> NodeStats {
+    computer
+        .children()
+        .with2()
+        .chain(computer.children().with4())
+        .map(|player| player.data.get())
+        .fold(NodeStats::default(), |acc, s| NodeStats {
+            visits: acc.visits + s.visits,
+            total_value: acc.total_value + s.total_value,
+        })
+}
+
+// The Upper Confidence Bound for Trees: exploitation (mean value so far) plus an exploration
+// bonus that shrinks as a child accumulates visits relative to its parent. Unvisited children
+// are always explored first.
+fn uct(stats: NodeStats, parent_visits: u32, exploration: f64) -> f64 {
+    if stats.visits == 0 {
+        return f64::INFINITY;
+    }
+
+    let mean_value = stats.total_value / f64::from(stats.visits);
+    let exploration_term =
+        exploration * (f64::from(parent_visits.max(1)).ln() / f64::from(stats.visits)).sqrt();
+
+    mean_value + exploration_term
+}
+
+fn sample<'a>(
+    mut children: impl Iterator<Item = PlayerNode<'a, NodeStats>> + Clone,
+    rng: &mut impl Rng,
+) -> PlayerNode<'a, NodeStats> {
+    let count = children.clone().count();
+    let index = rng.gen_range(0, count);
+    children.nth(index).unwrap()
+}
+
+// Plays uniformly random legal moves, spawning a tile after each one, until no legal move
+// remains, then scores the terminal board with `heuristic`.
+fn simulate(mut board: Board, heuristic: &impl Fn(Board) -> f64, rng: &mut impl Rng) -> f64 {
+    loop {
+        let legal_moves = board::MOVES
+            .iter()
+            .copied()
+            .filter(|&mv| board.make_move(mv) != board)
+            .collect::<Vec<_>>();
+
+        if legal_moves.is_empty() {
+            return heuristic(board);
+        }
+
+        let mv = legal_moves[rng.gen_range(0, legal_moves.len())];
+        let moved = board.make_move(mv);
+        let spawns = if rng.gen_bool(0.9) {
+            moved.ai_moves_with2().collect::<Vec<_>>()
+        } else {
+            moved.ai_moves_with4().collect::<Vec<_>>()
+        };
+        board = spawns[rng.gen_range(0, spawns.len())];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_tree::SearchTree;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // The number of empty tiles left on the board, as a heuristic: higher is better. Cheap and
+    // deterministic, which is all a regression test over a fixed seed needs.
+    fn empty_tiles_heuristic(board: Board) -> f64 {
+        board.unpack_human().iter().flatten().filter(|&&t| t == 0).count() as f64
+    }
+
+    #[test]
+    fn search_returns_a_legal_move_for_a_fixed_seed() {
+        let board = Board::from_u32([
+            [0, 0, 0, 2],
+            [0, 2, 0, 2],
+            [4, 0, 0, 2],
+            [0, 0, 0, 2],
+        ])
+        .unwrap();
+        let tree: SearchTree<NodeStats> = SearchTree::new(board);
+        let mut rng = StdRng::seed_from_u64(0xC0FFEE);
+
+        let mv = search(&tree, 200, 1.4, empty_tiles_heuristic, &mut rng);
+
+        let legal_moves = tree.root().children().iter().map(|(mv, _)| mv).collect::<Vec<_>>();
+        assert!(legal_moves.contains(&mv.expect("non-terminal root must return a move")));
+    }
+
+    #[test]
+    fn search_is_deterministic_for_a_fixed_seed() {
+        let board = Board::from_u32([
+            [0, 0, 0, 2],
+            [0, 2, 0, 2],
+            [4, 0, 0, 2],
+            [0, 0, 0, 2],
+        ])
+        .unwrap();
+
+        let run = || {
+            let tree: SearchTree<NodeStats> = SearchTree::new(board);
+            let mut rng = StdRng::seed_from_u64(0xC0FFEE);
+            search(&tree, 200, 1.4, empty_tiles_heuristic, &mut rng)
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn search_returns_none_for_a_game_over_root() {
+        let terminal =
+            Board::from_u32([[4, 16, 8, 4], [8, 128, 32, 2], [2, 32, 16, 8], [4, 2, 4, 2]])
+                .unwrap();
+        assert!(terminal.game_over());
+
+        let tree: SearchTree<NodeStats> = SearchTree::new(terminal);
+        let mut rng = StdRng::seed_from_u64(0xC0FFEE);
+
+        assert_eq!(None, search(&tree, 200, 1.4, empty_tiles_heuristic, &mut rng));
+    }
+}
@@ -0,0 +1,225 @@
+//! Evaluates each of the (up to four) legal root moves concurrently, each as its own
+//! depth-limited expectimax search, for near-linear speedup on deep searches.
+//!
+//! Rather than mirroring `search_tree`'s node graph into a second, disconnected implementation,
+//! this shares the very same `SearchTree`/`NodeCache` across worker threads: `best_move_parallel`
+//! takes a single short-lived lock just long enough to clone the root's `Arc<NodeCache<T>>` and
+//! read its `NodeId`, then drops it before handing each root move its own `ComputerNode` handle
+//! into that same cloned cache. From there every branch walks and memoizes nodes concurrently,
+//! synchronized only by `NodeCache`'s own per-node and per-index locks (see `search_tree::arena`
+//! and `search_tree::cache`) rather than by one coarse mutex held for an entire branch's
+//! recursion -- so work one branch's search memoizes is immediately available to every other
+//! branch too, instead of being duplicated across per-thread copies of the table, and without
+//! serializing the branches against each other the way holding a single lock across the whole
+//! recursive walk would.
+
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+
+use crate::board::{self, Board, Move};
+use crate::search_tree::{ComputerNode, PlayerNode, SearchTree};
+
+/// A thread-safe handle to a `SearchTree`, so its root moves can be evaluated concurrently by
+/// `best_move_parallel`.
+pub struct ParSearchTree<T>
+where
+    T: Copy + Default + Send,
+{
+    inner: Mutex<SearchTree<T>>,
+}
+
+impl<T> ParSearchTree<T>
+where
+    T: Copy + Default + Send,
+{
+    /// Creates a new `ParSearchTree` from an initial `Board` state.
+    pub fn new(board: Board) -> Self {
+        ParSearchTree {
+            inner: Mutex::new(SearchTree::new(board)),
+        }
+    }
+
+    /// Updates the search tree to have a different root `Board` state. See
+    /// `search_tree::SearchTree::set_root`.
+    pub fn set_root(&self, board: Board) {
+        self.inner.lock().unwrap().set_root(board);
+    }
+
+    /// Gets the number of known board states that the Player can face on their turn.
+    pub fn known_player_node_count(&self) -> usize {
+        self.inner.lock().unwrap().known_player_node_count()
+    }
+
+    /// Gets the number of known board states that the Computer can face on its turn.
+    pub fn known_computer_node_count(&self) -> usize {
+        self.inner.lock().unwrap().known_computer_node_count()
+    }
+}
+
+/// Evaluates each of the (up to four) legal moves from `tree`'s root concurrently, each as its
+/// own depth-limited expectimax search over the resulting subtree, and returns the move with the
+/// highest expected `heuristic` value. Returns `None` if the root is already game over.
+pub fn best_move_parallel<T>(
+    tree: &ParSearchTree<T>,
+    depth: usize,
+    heuristic: impl Fn(Board) -> f64 + Sync,
+) -> Option<Move>
+where
+    T: Copy + Default + Send,
+{
+    // Locked only long enough to clone the cache handle and read the root id: everything after
+    // this block runs against `cache` directly, with no further contention on `tree.inner`.
+    let (cache, root_id) = {
+        let guard = tree.inner.lock().unwrap();
+        (guard.cache.clone(), guard.root_id())
+    };
+
+    let root = PlayerNode::from_cache(&cache, root_id);
+
+    root.children()
+        .iter()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|(mv, computer)| (mv, expectimax_computer(computer, depth, &heuristic)))
+        .reduce_with(|a, b| if a.1 >= b.1 { a } else { b })
+        .map(|(mv, _)| mv)
+}
+
+fn expectimax_player<T>(
+    node: PlayerNode<'_, T>,
+    depth: usize,
+    heuristic: &(impl Fn(Board) -> f64 + Sync),
+) -> f64
+where
+    T: Copy + Default,
+{
+    let children = node.children();
+
+    if children.is_empty() || depth == 0 {
+        return heuristic(node.board());
+    }
+
+    children
+        .values()
+        .map(|computer| expectimax_computer(computer, depth - 1, heuristic))
+        .fold(f64::NEG_INFINITY, f64::max)
+}
+
+fn expectimax_computer<T>(
+    node: ComputerNode<'_, T>,
+    depth: usize,
+    heuristic: &(impl Fn(Board) -> f64 + Sync),
+) -> f64
+where
+    T: Copy + Default,
+{
+    let children = node.children();
+
+    let mean = |nodes: Vec<PlayerNode<'_, T>>| {
+        let count = nodes.len();
+        let total = nodes
+            .into_iter()
+            .map(|player| expectimax_player(player, depth, heuristic))
+            .sum::<f64>();
+
+        total / count as f64
+    };
+
+    0.9 * mean(children.with2().collect()) + 0.1 * mean(children.with4().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    // The number of empty tiles left on the board, as a heuristic: higher is better. Cheap and
+    // deterministic, which is all these tests need.
+    fn empty_tiles_heuristic(board: Board) -> f64 {
+        board.unpack_human().iter().flatten().filter(|&&t| t == 0).count() as f64
+    }
+
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn best_move_parallel_returns_a_legal_move() {
+        let board = Board::from_u32([
+            [0, 0, 0, 2],
+            [0, 2, 0, 2],
+            [4, 0, 0, 2],
+            [0, 0, 0, 2],
+        ]).unwrap();
+        let tree: ParSearchTree<()> = ParSearchTree::new(board);
+
+        let mv = best_move_parallel(&tree, 3, empty_tiles_heuristic);
+
+        let legal_moves = board::MOVES
+            .iter()
+            .copied()
+            .filter(|&mv| board.make_move(mv) != board)
+            .collect::<Vec<_>>();
+        assert!(legal_moves.contains(&mv.expect("non-terminal root must return a move")));
+    }
+
+    #[test]
+    fn best_move_parallel_returns_none_for_a_game_over_root() {
+        let terminal =
+            Board::from_u32([[4, 16, 8, 4], [8, 128, 32, 2], [2, 32, 16, 8], [4, 2, 4, 2]])
+                .unwrap();
+        assert!(terminal.game_over());
+
+        let tree: ParSearchTree<()> = ParSearchTree::new(terminal);
+
+        assert_eq!(None, best_move_parallel(&tree, 3, empty_tiles_heuristic));
+    }
+
+    #[test]
+    fn best_move_parallel_shares_memoized_nodes_across_branches() {
+        let board = Board::default().add_random_tile();
+        let tree: ParSearchTree<()> = ParSearchTree::new(board);
+
+        best_move_parallel(&tree, 2, empty_tiles_heuristic);
+
+        // If each root move were evaluated against its own, disconnected copy of the node graph
+        // rather than the one shared `SearchTree`, the shared tree would never learn of any node
+        // beyond its root, and neither count would rise above 1.
+        assert!(tree.known_player_node_count() > 1);
+        assert!(tree.known_computer_node_count() > 1);
+    }
+
+    #[test]
+    fn best_move_parallel_runs_branches_concurrently() {
+        // A heuristic that blocks, and counts how many calls are in flight at once. If
+        // `best_move_parallel` still held one coarse lock across an entire branch's recursion
+        // (the regression this guards against), every call into this heuristic would be fully
+        // serialized and `max_concurrent` would never rise above 1.
+        let board = Board::from_u32([
+            [0, 0, 0, 2],
+            [0, 2, 0, 2],
+            [4, 0, 0, 2],
+            [0, 0, 0, 2],
+        ])
+        .unwrap();
+        let tree: ParSearchTree<()> = ParSearchTree::new(board);
+
+        let in_flight = AtomicUsize::new(0);
+        let max_concurrent = AtomicUsize::new(0);
+
+        let blocking_heuristic = |board: Board| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_concurrent.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            empty_tiles_heuristic(board)
+        };
+
+        best_move_parallel(&tree, 1, blocking_heuristic);
+
+        assert!(
+            max_concurrent.load(Ordering::SeqCst) > 1,
+            "root moves should be evaluated concurrently, not serialized behind one lock"
+        );
+    }
+}
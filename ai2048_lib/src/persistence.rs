@@ -0,0 +1,177 @@
+//! Persists and reloads a `SearchTree`'s accumulated board evaluations across process runs.
+//!
+//! Every process start throws away all the work the transposition table accumulated. Instead
+//! of serializing the live node graph itself (which is full of arena indices and lazily-forced
+//! cells that don't make sense to round-trip), this persists a flat `Board -> T` table: just the
+//! evaluation data attached to each known board, which is all a future run needs in order to
+//! skip recomputing it. The archive is read back via `rkyv`, so a huge precomputed endgame
+//! table can be memory-mapped and queried without a full deserialization pass.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use bytecheck::CheckBytes;
+use memmap2::Mmap;
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Infallible, Serialize as RkyvSerialize};
+
+use crate::board::Board;
+use crate::search_tree::SearchTree;
+
+#[derive(Archive, RkyvSerialize, RkyvDeserialize)]
+#[archive(check_bytes)]
+struct Entry<T> {
+    board: Board,
+    data: T,
+}
+
+impl<T> SearchTree<T>
+where
+    T: Copy + Default + Archive + RkyvSerialize<AllocSerializer<4096>>,
+{
+    /// Serializes every known board state's accumulated `data` to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let entries = self
+            .cache
+            .player_entries()
+            .into_iter()
+            .map(|(board, data)| Entry { board, data })
+            .collect::<Vec<_>>();
+
+        let bytes = rkyv::to_bytes::<_, 4096>(&entries)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+        std::fs::write(path, bytes)
+    }
+}
+
+/// A memory-mapped `Board -> T` table previously written by `SearchTree::save`, queryable
+/// without a full deserialization pass.
+pub struct ArchivedTable<T> {
+    mmap: Mmap,
+    _marker: std::marker::PhantomData<T>,
+}
+
+impl<T> ArchivedTable<T>
+where
+    T: Archive,
+    for<'a> <Vec<Entry<T>> as Archive>::Archived: CheckBytes<DefaultValidator<'a>>,
+{
+    /// Memory-maps a table previously written by `SearchTree::save`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapped file is treated as read-only archive bytes for the lifetime of
+        // `self`; we never write through this mapping.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let table = ArchivedTable {
+            mmap,
+            _marker: std::marker::PhantomData,
+        };
+
+        // Validate eagerly, on open, rather than lazily on first use: a truncated, corrupted,
+        // or simply foreign file should fail loudly right here instead of causing UB (invalid
+        // enum discriminants, out-of-bounds reads) the first time something reads through it.
+        table.archived()?;
+
+        Ok(table)
+    }
+
+    fn archived(&self) -> io::Result<&<Vec<Entry<T>> as Archive>::Archived> {
+        rkyv::check_archived_root::<Vec<Entry<T>>>(&self.mmap).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupted transposition table archive: {err}"),
+            )
+        })
+    }
+
+    /// Populates `tree`'s transposition table with every entry in this archive, so subsequent
+    /// lookups for those boards hit the archived data instead of recomputing it.
+    pub fn populate(&self, tree: &SearchTree<T>) -> io::Result<()>
+    where
+        T: Copy + Default,
+        <T as Archive>::Archived: RkyvDeserialize<T, Infallible>,
+    {
+        for archived_entry in self.archived()?.iter() {
+            let board = archived_entry
+                .board
+                .deserialize(&mut Infallible)
+                .expect("infallible deserialization");
+            let data = archived_entry
+                .data
+                .deserialize(&mut Infallible)
+                .expect("infallible deserialization");
+
+            tree.cache.set_player_data(board, data);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "ai2048_lib_persistence_test_{}_{name}",
+            std::process::id()
+        ));
+        path
+    }
+
+    #[test]
+    fn save_then_load_round_trips_known_boards() {
+        let path = temp_path("round_trip");
+
+        let board = Board::default().add_random_tile();
+        let tree: SearchTree<u32> = SearchTree::new(board);
+        tree.cache.set_player_data(board, 42);
+
+        let child_board = tree
+            .root()
+            .children()
+            .values()
+            .next()
+            .and_then(|computer| computer.children().with2().next())
+            .map(|player| player.board())
+            .expect("root has at least one reachable grandchild");
+        tree.cache.set_player_data(child_board, 7);
+
+        tree.save(&path).unwrap();
+
+        let archive: ArchivedTable<u32> = ArchivedTable::open(&path).unwrap();
+        let loaded: SearchTree<u32> = SearchTree::new(board);
+        archive.populate(&loaded).unwrap();
+
+        let loaded_entries = loaded
+            .cache
+            .player_entries()
+            .into_iter()
+            .collect::<std::collections::HashMap<_, _>>();
+
+        assert_eq!(Some(&42), loaded_entries.get(&board));
+        assert_eq!(Some(&7), loaded_entries.get(&child_board));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn open_rejects_a_corrupted_archive() {
+        let path = temp_path("corrupted");
+
+        std::fs::write(&path, b"not a valid rkyv archive at all, just garbage bytes").unwrap();
+
+        let result = ArchivedTable::<u32>::open(&path);
+
+        assert!(result.is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+}
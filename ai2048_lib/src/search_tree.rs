@@ -3,29 +3,114 @@
 //!
 //! The types in this module generate its children only once.
 //!
-//! They use two different kinds of cache to reduce the amount of computation as much as possible:
+//! Nodes live in a pair of slab `Arena`s (one for `PlayerNode`s, one for `ComputerNode`s) rather
+//! than each being its own `Rc`-counted heap allocation; `PlayerNode`/`ComputerNode` are thin,
+//! `Copy` handles carrying a `NodeId` and a reference to the arenas. Two caches reduce the
+//! amount of computation as much as possible:
 //!
-//! 1. Each node stores references to its children.
-//! 2. When generating the children, the nodes query a `Cache` of known nodes (a transposition
-//! table) in case this same node has already been generated through a different set of moves.
-//!
-//! It achieves this by a combination of interior mutability, reference counted objects and
-//! a hashmap.
+//! 1. Each node stores the `ChildRange` its children were allocated into, so it only generates
+//!    them once.
+//! 2. When generating children, a node queries a `Cache` of known boards (a transposition
+//!    table) in case this same board has already been generated through a different set of
+//!    moves, resolving to the existing `NodeId` instead of allocating a new one.
 
+mod arena;
 mod cache;
+mod sync_cell;
+
+pub use cache::Backend;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use crate::board::{self, Board, Move};
+use crate::search_tree::arena::{Arena, ChildArena, ChildRange, NodeId};
 use crate::search_tree::cache::Cache;
-use lazycell::LazyCell;
-use std::cell::Cell;
-use std::rc::Rc;
+use crate::search_tree::sync_cell::SyncCell;
+
+struct PlayerSlot<T> {
+    board: Board,
+    children: Mutex<Option<ChildRange>>,
+    data: SyncCell<T>,
+}
+
+struct ComputerSlot {
+    board: Board,
+    children: Mutex<Option<ChildRange>>,
+}
+
+pub(crate) struct NodeCache<T>
+where
+    T: Copy + Default,
+{
+    backend: Backend,
+    player_arena: Arena<PlayerSlot<T>>,
+    computer_arena: Arena<ComputerSlot>,
+    children: ChildArena,
+    player_index: Cache<Board, NodeId>,
+    computer_index: Cache<Board, NodeId>,
+}
 
-struct NodeCache<T>
+impl<T> NodeCache<T>
 where
     T: Copy + Default,
 {
-    player_node: Cache<Board, PlayerNode<T>>,
-    computer_node: Cache<Board, ComputerNode<T>>,
+    fn new(backend: Backend, max_entries: Option<usize>) -> Self {
+        NodeCache {
+            backend,
+            player_arena: Arena::new(),
+            computer_arena: Arena::new(),
+            children: ChildArena::new(),
+            player_index: Cache::with_capacity(backend, max_entries),
+            computer_index: Cache::with_capacity(backend, max_entries),
+        }
+    }
+
+    /// Changes the maximum number of entries the player/computer board indices will each hold
+    /// going forward, evicting immediately if either is already over the new cap. See
+    /// `cache::Cache::set_max_entries` for what "evict" means here: it bounds the transposition
+    /// table, which is what actually grows unbounded over a long search, not the underlying node
+    /// arenas (those never shrink outside of `SearchTree::compact`).
+    fn set_max_entries(&self, max_entries: Option<usize>) {
+        self.player_index.set_max_entries(max_entries);
+        self.computer_index.set_max_entries(max_entries);
+    }
+
+    fn get_or_create_player(&self, board: Board) -> NodeId {
+        self.player_index.get_or_insert_with(board, || {
+            self.player_arena.alloc(PlayerSlot {
+                board,
+                children: Mutex::new(None),
+                data: SyncCell::new(T::default()),
+            })
+        })
+    }
+
+    fn get_or_create_computer(&self, board: Board) -> NodeId {
+        self.computer_index.get_or_insert_with(board, || {
+            self.computer_arena.alloc(ComputerSlot {
+                board,
+                children: Mutex::new(None),
+            })
+        })
+    }
+
+    /// A snapshot of every known `(Board, T)` pair in the player-node table, e.g. for
+    /// persistence to walk.
+    pub(crate) fn player_entries(&self) -> Vec<(Board, T)> {
+        self.player_index
+            .snapshot()
+            .into_iter()
+            .map(|(board, id)| (board, self.player_arena.get(id).data.get()))
+            .collect()
+    }
+
+    /// Inserts (or overwrites) the `data` attached to `board`'s player node, creating it if it
+    /// doesn't already exist. Used to repopulate the table from a persisted archive.
+    pub(crate) fn set_player_data(&self, board: Board, data: T) {
+        let id = self.get_or_create_player(board);
+        self.player_arena.get(id).data.set(data);
+    }
 }
 
 /// The `SearchTree` type is the root of the tree of nodes that form all possible board states in
@@ -37,91 +122,205 @@ pub struct SearchTree<T>
 where
     T: Copy + Default,
 {
-    root_node: Rc<PlayerNode<T>>,
-    cache: Rc<NodeCache<T>>,
+    root: NodeId,
+    // `Arc` rather than `Rc`: the arenas and indices it owns are genuinely `Sync` whenever `T` is
+    // `Send` (their interior mutability is `Mutex`/`RwLock`-backed, not `Cell`/`RefCell`), so a
+    // cloned `Arc<NodeCache<T>>` can be handed to another thread and read or extended
+    // concurrently -- which is what lets `parallel` evaluate root moves genuinely in parallel
+    // instead of serializing them behind one lock held for an entire search.
+    pub(crate) cache: Arc<NodeCache<T>>,
+    max_entries: Option<usize>,
 }
 
 impl<T> SearchTree<T>
 where
     T: Copy + Default,
 {
-    /// Creates a new `SearchTree` from an initial `Board` state.
+    /// Creates a new `SearchTree` from an initial `Board` state, with an unbounded transposition
+    /// table.
     pub fn new(board: Board) -> Self {
-        let cache = Rc::new(NodeCache {
-            player_node: Cache::new(),
-            computer_node: Cache::new(),
-        });
+        Self::with_capacity(board, None)
+    }
+
+    /// As `new`, but caps the player/computer board index at `max_entries` entries each,
+    /// evicting the least-recently-touched one on every insert once full (see
+    /// `cache::Cache::get_or_insert_with`). This bounds the transposition table's growth even in
+    /// the middle of a single deep search, without ever calling `set_root`. Pass `None` for the
+    /// previous, unbounded behavior.
+    pub fn with_capacity(board: Board, max_entries: Option<usize>) -> Self {
+        Self::with_backend(board, Backend::HashMap, max_entries)
+    }
 
-        let node = cache
-            .player_node
-            .get_or_insert_with(board, || PlayerNode::new(board, cache.clone()));
+    /// As `with_capacity`, but lets the caller pick the node table's storage `Backend` instead
+    /// of defaulting to a hashed table.
+    pub fn with_backend(board: Board, backend: Backend, max_entries: Option<usize>) -> Self {
+        let cache = Arc::new(NodeCache::new(backend, max_entries));
+        let root = cache.get_or_create_player(board);
 
         SearchTree {
-            root_node: node,
+            root,
             cache,
+            max_entries,
         }
     }
 
+    /// Changes the maximum number of entries the player/computer board index may each hold,
+    /// evicting immediately if either is already over the new cap. See `with_capacity`.
+    pub fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+        self.cache.set_max_entries(max_entries);
+    }
+
     /// Updates the search tree to have a different root `Board` state. It has an advantage over
-    /// creating a new one because it reuses the inner cache of known nodes. This implicitly
-    /// invalidates now unreachable board states in the cache (or at least board states that
-    /// have no known way to be reached). This also explicitly cleans up the invalidated keys
-    /// from the cache.
+    /// creating a new one because it reuses the inner table of known nodes. This rebases every
+    /// node still reachable from the new root into a fresh pair of arenas, and drops the rest in
+    /// bulk, rather than relying on a per-entry reference count and incremental collection.
     pub fn set_root(&mut self, board: Board) {
-        let node = self
-            .cache
-            .player_node
-            .get_or_insert_with(board, || PlayerNode::new(board, self.cache.clone()));
-
-        self.root_node = node;
-
-        self.clean_up_cache();
+        let root = self.cache.get_or_create_player(board);
+        self.root = root;
+        self.compact();
     }
 
     /// Gets a reference to the current root node.
-    pub fn root(&self) -> &PlayerNode<T> {
-        self.root_node.as_ref()
+    pub fn root(&self) -> PlayerNode<'_, T> {
+        PlayerNode::from_cache(&self.cache, self.root)
     }
 
-    /// Gets the number of known board states that the Player can face on their turn.
+    /// The current root's `NodeId`, for `parallel` to pair with a cloned `Arc<NodeCache<T>>` and
+    /// reconstruct a `PlayerNode` without holding this `SearchTree`'s lock for the whole search.
+    pub(crate) fn root_id(&self) -> NodeId {
+        self.root
+    }
+
+    /// Gets the number of known board states that the Player can face on their turn. This is the
+    /// node arena's size, which (unlike the transposition table `max_entries` bounds) only ever
+    /// shrinks on `set_root`'s compaction.
     pub fn known_player_node_count(&self) -> usize {
-        self.cache.player_node.strong_count()
+        self.cache.player_arena.len()
     }
 
-    /// Gets the number of known board states that the Computer can face on its turn.
+    /// Gets the number of known board states that the Computer can face on its turn. As
+    /// `known_player_node_count`, this is the node arena's size, not the (possibly smaller,
+    /// `max_entries`-capped) transposition table.
     pub fn known_computer_node_count(&self) -> usize {
-        self.cache.computer_node.strong_count()
+        self.cache.computer_arena.len()
+    }
+
+    // Walks every node reachable from the current root, copying each into a fresh `NodeCache`
+    // (preserving already-computed children and `data`, so no memoized work is lost), then
+    // swaps that in as `self.cache`. Everything unreachable is simply never copied over, and is
+    // freed in bulk when the old, now-unreferenced `Arc<NodeCache<T>>` drops. This is what bounds
+    // the arenas themselves; it runs unconditionally on `set_root` rather than being gated on
+    // `max_entries`, since `max_entries` is enforced continuously by the transposition table
+    // (see `NodeCache::set_max_entries`) regardless of whether `set_root` is ever called.
+    fn compact(&mut self) {
+        let fresh = NodeCache::new(self.cache.backend, self.max_entries);
+        let mut player_map = HashMap::new();
+        let mut computer_map = HashMap::new();
+
+        let new_root = rebase_player(&self.cache, &fresh, self.root, &mut player_map, &mut computer_map);
+
+        self.cache = Arc::new(fresh);
+        self.root = new_root;
+    }
+}
+
+fn rebase_player<T>(
+    old: &NodeCache<T>,
+    fresh: &NodeCache<T>,
+    id: NodeId,
+    player_map: &mut HashMap<NodeId, NodeId>,
+    computer_map: &mut HashMap<NodeId, NodeId>,
+) -> NodeId
+where
+    T: Copy + Default,
+{
+    if let Some(&mapped) = player_map.get(&id) {
+        return mapped;
+    }
+
+    let slot = old.player_arena.get(id);
+    let new_id = fresh.get_or_create_player(slot.board);
+    fresh.player_arena.get(new_id).data.set(slot.data.get());
+    player_map.insert(id, new_id);
+
+    let children = *slot.children.lock().unwrap();
+    if let Some(range) = children {
+        let mut slots = [None; 4];
+        for (index, slot) in slots.iter_mut().enumerate() {
+            if let Some(child) = old.children.get(range, index) {
+                *slot = Some(rebase_computer(old, fresh, child, player_map, computer_map));
+            }
+        }
+
+        let new_range = fresh.children.alloc(slots);
+        *fresh.player_arena.get(new_id).children.lock().unwrap() = Some(new_range);
     }
 
-    fn clean_up_cache(&self) {
-        self.cache.player_node.gc();
-        self.cache.computer_node.gc();
+    new_id
+}
+
+fn rebase_computer<T>(
+    old: &NodeCache<T>,
+    fresh: &NodeCache<T>,
+    id: NodeId,
+    player_map: &mut HashMap<NodeId, NodeId>,
+    computer_map: &mut HashMap<NodeId, NodeId>,
+) -> NodeId
+where
+    T: Copy + Default,
+{
+    if let Some(&mapped) = computer_map.get(&id) {
+        return mapped;
+    }
+
+    let slot = old.computer_arena.get(id);
+    let new_id = fresh.get_or_create_computer(slot.board);
+    computer_map.insert(id, new_id);
+
+    let children = *slot.children.lock().unwrap();
+    if let Some(range) = children {
+        let count = old.children.len(range) / 2;
+        let rebased = (0..count * 2)
+            .map(|i| old.children.get(range, i))
+            .map(|child| child.map(|child| rebase_player(old, fresh, child, player_map, computer_map)))
+            .collect::<Vec<_>>();
+
+        let new_range = fresh.children.alloc(rebased);
+        *fresh.computer_arena.get(new_id).children.lock().unwrap() = Some(new_range);
     }
+
+    new_id
 }
 
 /// This type represents the children of a `PlayerNode`.
-pub struct PlayerNodeChildren<T>
+#[derive(Copy, Clone)]
+pub struct PlayerNodeChildren<'a, T>
 where
     T: Copy + Default,
 {
-    nodes: [Option<Rc<ComputerNode<T>>>; 4],
+    tree: &'a NodeCache<T>,
+    range: ChildRange,
 }
 
-impl<T> PlayerNodeChildren<T>
+impl<'a, T> PlayerNodeChildren<'a, T>
 where
     T: Copy + Default,
 {
     /// Returns true if there are no children. This is true for a game over node's children.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.nodes.iter().all(|n| n.is_none())
+        (0..4).all(|index| self.tree.children.get(self.range, index).is_none())
     }
 
-    /// Iterates over children, returning `(Move, &ComputerNode)` tuples.
+    /// Iterates over children, returning `(Move, ComputerNode)` pairs.
     #[inline]
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Move, &'a ComputerNode<T>)> + 'a {
-        self.nodes.iter().enumerate().filter_map(|(index, opt)| {
-            opt.as_ref().map(|node| {
+    pub fn iter(&self) -> impl Iterator<Item = (Move, ComputerNode<'a, T>)> + 'a {
+        let tree = self.tree;
+        let range = self.range;
+
+        (0..4u8).filter_map(move |index| {
+            tree.children.get(range, index as usize).map(|id| {
                 let mv = match index {
                     0 => Move::Left,
                     1 => Move::Right,
@@ -130,77 +329,85 @@ where
                     _ => unreachable!(),
                 };
 
-                (mv, node.as_ref())
+                (mv, ComputerNode::from_cache(tree, id))
             })
         })
     }
 
-    /// Iterates over children, returning `&ComputerNode`s without moves.
+    /// Iterates over children, returning `ComputerNode`s without moves.
     #[inline]
-    pub fn values<'a>(&'a self) -> impl Iterator<Item = &'a ComputerNode<T>> + 'a {
-        self.nodes
-            .iter()
-            .filter_map(|opt| opt.as_ref().map(|node| node.as_ref()))
+    pub fn values(&self) -> impl Iterator<Item = ComputerNode<'a, T>> + 'a {
+        self.iter().map(|(_, node)| node)
     }
 }
 
-/// This type represents a `Board` state that can be reached on the Player's turn. This type
-/// is logically immutable, and there should be no way to create this type from outside the module
-/// through any means other than querying the `SearchTree` root and its descendants.
-///
-/// However, this type makes use of interior mutability to defer generating its children until
-/// such time as it is asked to do so, and only do it once even then.
-pub struct PlayerNode<T>
+/// This type represents a `Board` state that can be reached on the Player's turn. It is a thin,
+/// `Copy` handle into the owning `SearchTree`'s node arena; there should be no way to create one
+/// from outside the module other than querying the `SearchTree` root and its descendants.
+#[derive(Copy, Clone)]
+pub struct PlayerNode<'a, T>
 where
     T: Copy + Default,
 {
-    board: Board,
-    cache: Rc<NodeCache<T>>,
-    children: LazyCell<PlayerNodeChildren<T>>,
-    pub data: Cell<T>,
+    id: NodeId,
+    tree: &'a NodeCache<T>,
+    /// Per-node data, e.g. a heuristic evaluation or search statistics. `pub(crate)` rather than
+    /// `pub`: its `SyncCell<T>` type is crate-private, and nothing outside `mcts`/`persistence`
+    /// needs to reach it.
+    pub(crate) data: &'a SyncCell<T>,
 }
 
-impl<T> PlayerNode<T>
+impl<'a, T> PlayerNode<'a, T>
 where
     T: Copy + Default,
 {
-    fn new(board: Board, cache: Rc<NodeCache<T>>) -> Self {
+    /// Builds a handle into `tree` for the node at `id`. `pub(crate)` so `parallel` can hand a
+    /// cloned `Arc<NodeCache<T>>` straight to a worker thread and rebuild a handle from it,
+    /// without needing to keep the owning `SearchTree`'s lock held for the rest of the search.
+    pub(crate) fn from_cache(tree: &'a NodeCache<T>, id: NodeId) -> Self {
         PlayerNode {
-            board,
-            cache,
-            children: LazyCell::new(),
-            data: Cell::new(T::default()),
+            id,
+            tree,
+            data: &tree.player_arena.get(id).data,
         }
     }
 
-    /// Get a reference to the `Board` state associated with this node.
-    pub fn board(&self) -> &Board {
-        &self.board
+    fn slot(&self) -> &'a PlayerSlot<T> {
+        self.tree.player_arena.get(self.id)
+    }
+
+    /// Get the `Board` state associated with this node.
+    pub fn board(&self) -> Board {
+        self.slot().board
     }
 
     /// Returns a `PlayerNodeChildren` which represents all possible `Move`:`ComputerNode` pairs
     /// possible in the current position.
-    pub fn children(&self) -> &PlayerNodeChildren<T> {
-        self.children.borrow_with(|| self.create_children())
+    pub fn children(&self) -> PlayerNodeChildren<'a, T> {
+        let slot = self.slot();
+        let mut guard = slot.children.lock().unwrap();
+        let range = *guard.get_or_insert_with(|| self.create_children());
+
+        PlayerNodeChildren {
+            tree: self.tree,
+            range,
+        }
     }
 
-    fn create_children(&self) -> PlayerNodeChildren<T> {
-        let mut children = [None, None, None, None];
+    fn create_children(&self) -> ChildRange {
+        let board = self.slot().board;
+        let mut slots = [None; 4];
 
         for &m in &board::MOVES {
-            let new_grid = self.board.make_move(m);
+            let new_board = board.make_move(m);
 
             // It is illegal to make a move that doesn't change anything.
-            if new_grid != self.board {
-                let computer_node = self.cache.computer_node.get_or_insert_with(new_grid, || {
-                    ComputerNode::new(new_grid, self.cache.clone())
-                });
-
-                children[m as u8 as usize] = Some(computer_node);
+            if new_board != board {
+                slots[m as u8 as usize] = Some(self.tree.get_or_create_computer(new_board));
             }
         }
 
-        PlayerNodeChildren { nodes: children }
+        self.tree.children.alloc(slots)
     }
 }
 
@@ -208,105 +415,118 @@ where
 /// that were generated by spawning a 2 from ones that were spawned with a 4, because in a game
 /// of 2048 a 4 only spawns 10% of the time, and it's important to take into account how likely
 /// an outcome is.
-pub struct ComputerNodeChildren<T>
+#[derive(Copy, Clone)]
+pub struct ComputerNodeChildren<'a, T>
 where
     T: Copy + Default,
 {
-    with2: Vec<Rc<PlayerNode<T>>>,
-    with4: Vec<Rc<PlayerNode<T>>>,
+    tree: &'a NodeCache<T>,
+    range: ChildRange,
 }
 
-impl<T> ComputerNodeChildren<T>
+impl<'a, T> ComputerNodeChildren<'a, T>
 where
     T: Copy + Default,
 {
+    fn variant_count(&self) -> usize {
+        self.tree.children.len(self.range) / 2
+    }
+
     /// Game states generated by the computer spawning a 2.
     #[inline]
-    pub fn with2<'a>(&'a self) -> impl Iterator<Item = &'a PlayerNode<T>> + 'a {
-        self.with2.iter().map(|n| n.as_ref())
+    pub fn with2(&self) -> impl Iterator<Item = PlayerNode<'a, T>> + 'a {
+        let tree = self.tree;
+        let range = self.range;
+
+        (0..self.variant_count()).map(move |index| {
+            let id = tree
+                .children
+                .get(range, index)
+                .expect("with2 slots are always populated");
+            PlayerNode::from_cache(tree, id)
+        })
     }
 
     /// Game states generated by the computer spawning a 4.
     #[inline]
-    pub fn with4<'a>(&'a self) -> impl Iterator<Item = &'a PlayerNode<T>> + 'a {
-        self.with4.iter().map(|n| n.as_ref())
+    pub fn with4(&self) -> impl Iterator<Item = PlayerNode<'a, T>> + 'a {
+        let tree = self.tree;
+        let range = self.range;
+        let count = self.variant_count();
+
+        (0..count).map(move |index| {
+            let id = tree
+                .children
+                .get(range, count + index)
+                .expect("with4 slots are always populated");
+            PlayerNode::from_cache(tree, id)
+        })
     }
 
     /// Number of variants of either children
     pub fn variants(&self) -> usize {
-        self.with2.len()
+        self.variant_count()
     }
 }
 
-/// This type represents a `Board` state that can be reached on the Computer's turn. This type
-/// is logically immutable, and there should be no way to create this type from outside the module
-/// through any means other than querying a `PlayerNode`.
-///
-/// However, this type makes use of interior mutability to defer generating its children until
-/// such time as it is asked to do so, and only do it once even then.
-pub struct ComputerNode<T>
+/// This type represents a `Board` state that can be reached on the Computer's turn. As
+/// `PlayerNode`, it is a thin, `Copy` handle into the owning `SearchTree`'s node arena.
+#[derive(Copy, Clone)]
+pub struct ComputerNode<'a, T>
 where
     T: Copy + Default,
 {
-    board: Board,
-    cache: Rc<NodeCache<T>>,
-    children: LazyCell<ComputerNodeChildren<T>>,
+    id: NodeId,
+    tree: &'a NodeCache<T>,
 }
 
-impl<T> ComputerNode<T>
+impl<'a, T> ComputerNode<'a, T>
 where
     T: Copy + Default,
 {
-    fn new(board: Board, cache: Rc<NodeCache<T>>) -> Self {
-        ComputerNode {
-            board,
-            cache,
-            children: LazyCell::new(),
-        }
+    /// Builds a handle into `tree` for the node at `id`. See `PlayerNode::from_cache` for why
+    /// this is `pub(crate)` rather than private.
+    pub(crate) fn from_cache(tree: &'a NodeCache<T>, id: NodeId) -> Self {
+        ComputerNode { id, tree }
+    }
+
+    fn slot(&self) -> &'a ComputerSlot {
+        self.tree.computer_arena.get(self.id)
     }
 
-    /// Get a reference to the `Board` state associated with this node.
-    pub fn board(&self) -> &Board {
-        &self.board
+    /// Get the `Board` state associated with this node.
+    pub fn board(&self) -> Board {
+        self.slot().board
     }
 
     /// Returns an `ComputerNodeChildren` that represents all possible states that the Player
-    /// can face following a computer spawning a random 2 or 4 tile. Can't be empty, by the game'search_tree
+    /// can face following a computer spawning a random 2 or 4 tile. Can't be empty, by the game's
     /// logic.
-    pub fn children(&self) -> &ComputerNodeChildren<T> {
-        self.children.borrow_with(|| self.create_children())
+    pub fn children(&self) -> ComputerNodeChildren<'a, T> {
+        let slot = self.slot();
+        let mut guard = slot.children.lock().unwrap();
+        let range = *guard.get_or_insert_with(|| self.create_children());
+
+        ComputerNodeChildren {
+            tree: self.tree,
+            range,
+        }
     }
 
-    fn create_children(&self) -> ComputerNodeChildren<T> {
-        let children_with2 = self
-            .board
-            .ai_moves_with2()
-            .into_iter()
-            .map(|board| {
-                self.cache
-                    .player_node
-                    .get_or_insert_with(board, || PlayerNode::new(board, self.cache.clone()))
-            })
-            .collect::<Vec<_>>();
+    fn create_children(&self) -> ChildRange {
+        let board = self.slot().board;
 
-        let children_with4 = self
-            .board
+        let with2 = board
+            .ai_moves_with2()
+            .map(|board| Some(self.tree.get_or_create_player(board)));
+        let with4 = board
             .ai_moves_with4()
-            .into_iter()
-            .map(|board| {
-                self.cache
-                    .player_node
-                    .get_or_insert_with(board, || PlayerNode::new(board, self.cache.clone()))
-            })
-            .collect::<Vec<_>>();
+            .map(|board| Some(self.tree.get_or_create_player(board)));
 
-        debug_assert!(!children_with2.is_empty());
-        debug_assert!(!children_with4.is_empty());
+        let slots = with2.chain(with4).collect::<Vec<_>>();
+        debug_assert!(!slots.is_empty());
 
-        ComputerNodeChildren {
-            with2: children_with2,
-            with4: children_with4,
-        }
+        self.tree.children.alloc(slots)
     }
 }
 
@@ -320,7 +540,7 @@ mod tests {
     fn can_create_new_search_tree() {
         let expected_grid = Board::default().add_random_tile();
         let search_tree: SearchTree<()> = SearchTree::new(expected_grid);
-        let actual_grid = *search_tree.root().board();
+        let actual_grid = search_tree.root().board();
 
         assert_eq!(expected_grid, actual_grid);
     }
@@ -333,10 +553,46 @@ mod tests {
 
         search_tree.set_root(grid2);
 
-        assert_eq!(grid2, *search_tree.root().board());
+        assert_eq!(grid2, search_tree.root().board());
         assert_eq!(1, search_tree.known_player_node_count());
-        let total = search_tree.cache.player_node.len();
-        assert_eq!(1, total);
+    }
+
+    #[test]
+    fn cache_eviction_does_not_corrupt_the_live_graph() {
+        let board = Board::default().add_random_tile();
+        let mut search_tree: SearchTree<()> = SearchTree::with_capacity(board, Some(1));
+
+        // Forcing the root's children evicts every other entry out of the board index (cap 1),
+        // including boards still reachable from the root; since a `PlayerNode`'s children are
+        // read back from its own `ChildRange` rather than re-looked-up in the (now-evicted)
+        // index, this must not corrupt or lose the already-built live graph.
+        let child_count = search_tree.root().children().values().count();
+
+        assert!(child_count >= 1);
+
+        search_tree.set_max_entries(Some(1));
+
+        assert_eq!(board, search_tree.root().board());
+        assert_eq!(child_count, search_tree.root().children().values().count());
+    }
+
+    #[test]
+    fn table_eviction_bounds_the_index_during_a_single_search() {
+        let board = Board::default().add_random_tile();
+        let search_tree: SearchTree<()> = SearchTree::with_capacity(board, Some(2));
+
+        // Visits every reachable node two plies deep without ever calling `set_root`. If the
+        // cap were only enforced at `set_root`/`set_max_entries` boundaries (the regression this
+        // guards against), the board index would grow well past it here instead of staying
+        // bounded throughout the search.
+        for (_, computer) in search_tree.root().children().iter() {
+            for player in computer.children().with2().chain(computer.children().with4()) {
+                player.children();
+            }
+        }
+
+        assert!(search_tree.cache.player_index.len() <= 2);
+        assert!(search_tree.cache.computer_index.len() <= 2);
     }
 
     #[test]
@@ -382,13 +638,38 @@ mod tests {
         let actual = player_node.children().iter().collect::<HashMap<_, _>>();
 
         for (key, value) in expected {
-            assert_eq!(value, *actual.get(&key).unwrap().board());
+            assert_eq!(value, actual.get(&key).unwrap().board());
         }
 
         assert_eq!(1, search_tree.known_player_node_count());
         assert_eq!(4, search_tree.known_computer_node_count());
     }
 
+    #[test]
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    fn radix_trie_backend_produces_same_children_as_hashmap_backend() {
+        let board = Board::from_u32([
+            [0, 0, 0, 2],
+            [0, 2, 0, 2],
+            [4, 0, 0, 2],
+            [0, 0, 0, 2],
+        ]).unwrap();
+
+        let hashmap_tree: SearchTree<()> = SearchTree::with_backend(board, Backend::HashMap, None);
+        let radix_tree: SearchTree<()> = SearchTree::with_backend(board, Backend::RadixTrie, None);
+
+        let hashmap_children = hashmap_tree.root().children().iter()
+            .map(|(_, node)| node.board())
+            .collect::<HashSet<_>>();
+        let radix_children = radix_tree.root().children().iter()
+            .map(|(_, node)| node.board())
+            .collect::<HashSet<_>>();
+
+        assert_eq!(hashmap_children, radix_children);
+        assert_eq!(hashmap_tree.known_player_node_count(), radix_tree.known_player_node_count());
+        assert_eq!(hashmap_tree.known_computer_node_count(), radix_tree.known_computer_node_count());
+    }
+
     #[test]
     #[cfg_attr(rustfmt, rustfmt_skip)]
     fn can_computer_node_children() {
@@ -468,17 +749,17 @@ mod tests {
             .children()
             .values()
             .flat_map(|v| v.children().with2())
-            .map(|n| *n.board())
+            .map(|n| n.board())
             .collect::<HashSet<_>>();
 
         let actual_with4 = search_tree.root()
             .children()
             .values()
             .flat_map(|v| v.children().with4())
-            .map(|n| *n.board())
+            .map(|n| n.board())
             .collect::<HashSet<_>>();
 
         assert_eq!(expected_with2, actual_with2);
         assert_eq!(expected_with4, actual_with4);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,95 @@
+//! An append-only slab arena: nodes live contiguously, addressed by an integer `NodeId` instead
+//! of each being its own heap allocation behind an `Rc`. This trades the pointer-chasing and
+//! per-node allocator traffic of an `Rc`-linked graph for a single growable backing store and
+//! index arithmetic.
+//!
+//! Every entry is boxed individually so that growing the arena's backing `Vec` never moves (and
+//! so never invalidates) a `&T` handed out earlier: growth only relocates the `Box<T>` pointers,
+//! never the `T` values they point to.
+//!
+//! Storage is behind a `RwLock` rather than a `RefCell`, so an `Arena` can be shared across
+//! threads (see `search_tree::NodeCache`, which `parallel` shares through a cloned `Arc` and
+//! whose arenas are read and extended concurrently by every worker thread).
+
+use std::sync::RwLock;
+
+/// An index into an `Arena`. Only meaningful alongside the specific `Arena` that produced it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct NodeId(u32);
+
+pub(crate) struct Arena<T> {
+    nodes: RwLock<Vec<Box<T>>>,
+}
+
+impl<T> Arena<T> {
+    pub(crate) fn new() -> Self {
+        Arena {
+            nodes: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Appends `value`, returning the `NodeId` it can be looked up by.
+    pub(crate) fn alloc(&self, value: T) -> NodeId {
+        let mut nodes = self.nodes.write().unwrap();
+        let id = NodeId(nodes.len() as u32);
+        nodes.push(Box::new(value));
+        id
+    }
+
+    /// Looks up a previously-allocated node. Panics if `id` didn't come from this `Arena`.
+    pub(crate) fn get(&self, id: NodeId) -> &T {
+        let nodes = self.nodes.read().unwrap();
+        let node: &T = &nodes[id.0 as usize];
+
+        // SAFETY: `node` borrows the `Box<T>` stored at this slot. Appending more nodes may
+        // reallocate `self.nodes`' backing storage and move the `Box<T>` pointers around, but
+        // never the `T` values they point to, and entries are never removed or replaced once
+        // written. So extending this reference's lifetime to match `self`'s, rather than the
+        // short-lived `RwLockReadGuard` above, doesn't let it outlive the data it points to.
+        unsafe { &*(node as *const T) }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.read().unwrap().len()
+    }
+}
+
+/// A contiguous run of child slots inside a `ChildArena`. Cheap, `Copy` descriptor rather than
+/// an owned `Vec`, since the slots themselves live in the shared arena.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct ChildRange {
+    start: u32,
+    len: u32,
+}
+
+/// A flat store of child-node references, indexed into by `ChildRange`s handed out by `alloc`.
+/// `PlayerNode` reserves a fixed 4 slots (one per `Move`, `None` where that move is illegal);
+/// `ComputerNode` reserves `2 * variants` slots (the spawn-a-2 children, then the spawn-a-4
+/// children).
+pub(crate) struct ChildArena {
+    slots: RwLock<Vec<Option<NodeId>>>,
+}
+
+impl ChildArena {
+    pub(crate) fn new() -> Self {
+        ChildArena {
+            slots: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn alloc(&self, slots: impl IntoIterator<Item = Option<NodeId>>) -> ChildRange {
+        let mut store = self.slots.write().unwrap();
+        let start = store.len() as u32;
+        store.extend(slots);
+        let len = store.len() as u32 - start;
+        ChildRange { start, len }
+    }
+
+    pub(crate) fn get(&self, range: ChildRange, index: usize) -> Option<NodeId> {
+        self.slots.read().unwrap()[range.start as usize + index]
+    }
+
+    pub(crate) fn len(&self, range: ChildRange) -> usize {
+        range.len as usize
+    }
+}
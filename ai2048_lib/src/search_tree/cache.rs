@@ -0,0 +1,267 @@
+//! Maps `Board` states to the `NodeId` of their slot in the corresponding node arena, so a
+//! board state reached via two different move sequences resolves to the same arena slot.
+//!
+//! Two storage `Backend`s are available, chosen once at construction. The default hashes the
+//! key into a `HashMap`. The alternative, `RadixTrie`, indexes directly on the key's packed bit
+//! pattern instead: a `Board` packs sixteen tiles into sixteen 4-bit nibbles, so the trie
+//! descends one nibble per level through sixteen levels of 16-wide node arrays to a leaf,
+//! without ever hashing the key.
+//!
+//! A `Cache` can also be given a `max_entries` cap, evicting the least-recently-touched entry
+//! on every insert once it's full (see `get_or_insert_with`). Unlike the `Rc`-counted table this
+//! replaced, eviction here is unconditional rather than skipping still-referenced entries: since
+//! `NodeId`s are plain, cheap-to-copy integers into an append-only `Arena` rather than
+//! `Rc`-counted pointers, there's no reachability count to consult, and an evicted board is
+//! simply looked up again (allocating a fresh arena slot) the next time something needs it. This
+//! bounds the table itself -- the actual driver of unbounded memory growth during a long search --
+//! even mid-search, without `SearchTree` ever calling `set_root`; it does not bound the
+//! underlying arenas, which (like any append-only slab) never shrink, so a board evicted from
+//! here and re-derived later leaves its old arena slot behind as harmless, unreachable-from-here
+//! garbage until the next `SearchTree::compact`.
+//!
+//! Storage is behind a `Mutex` rather than a `RefCell` so a `Cache` can be shared across threads
+//! (see `search_tree::NodeCache`).
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::board::Board;
+use crate::search_tree::arena::NodeId;
+
+/// Which storage strategy a `Cache` uses internally. Both behave identically from the outside;
+/// this only trades off how entries are indexed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Backend {
+    /// Hashes the key into a `std::collections::HashMap`. The default.
+    HashMap,
+    /// Indexes directly on the key's `packed_bits`, one 4-bit nibble per level of a radix trie.
+    /// No hashing, and boards sharing a bit prefix share trie nodes.
+    RadixTrie,
+}
+
+/// A key that can be decomposed into the fixed-width packed representation a `RadixTrie`
+/// backend indexes on.
+pub(crate) trait PackedKey {
+    fn packed_bits(self) -> u64;
+}
+
+impl PackedKey for Board {
+    fn packed_bits(self) -> u64 {
+        self.bits()
+    }
+}
+
+// A `Board`'s sixteen tiles are packed one per nibble, so sixteen 4-bit levels exhaust the key.
+const TRIE_LEVELS: usize = 16;
+const TRIE_WIDTH: usize = 16;
+
+fn nibble(bits: u64, level: usize) -> usize {
+    ((bits >> (level * 4)) & 0xF) as usize
+}
+
+struct RadixNode<K, V> {
+    children: [Option<Box<RadixNode<K, V>>>; TRIE_WIDTH],
+    leaf: Option<(K, V)>,
+}
+
+impl<K, V> RadixNode<K, V>
+where
+    V: Copy,
+{
+    fn empty() -> Self {
+        RadixNode {
+            children: std::array::from_fn(|_| None),
+            leaf: None,
+        }
+    }
+
+    fn get_or_insert_with(&mut self, bits: u64, key: K, default: impl FnOnce() -> V) -> V {
+        let mut node = self;
+        for level in 0..TRIE_LEVELS {
+            let slot = &mut node.children[nibble(bits, level)];
+            if slot.is_none() {
+                *slot = Some(Box::new(RadixNode::empty()));
+            }
+            node = slot.as_deref_mut().unwrap();
+        }
+
+        node.leaf.get_or_insert_with(|| (key, default())).1
+    }
+
+    fn len(&self) -> usize {
+        let own = usize::from(self.leaf.is_some());
+        own + self
+            .children
+            .iter()
+            .flatten()
+            .map(|child| child.len())
+            .sum::<usize>()
+    }
+
+    fn snapshot_into(&self, out: &mut Vec<(K, V)>)
+    where
+        K: Copy,
+    {
+        if let Some(entry) = self.leaf {
+            out.push(entry);
+        }
+        for child in self.children.iter().flatten() {
+            child.snapshot_into(out);
+        }
+    }
+
+    // Removes the entry at `bits`, if any, pruning any branch left with no descendants so an
+    // evicted entry doesn't leave permanent, ever-growing dead weight behind. Returns whether an
+    // entry was actually removed.
+    fn remove(&mut self, bits: u64, level: usize) -> bool {
+        if level == TRIE_LEVELS {
+            return self.leaf.take().is_some();
+        }
+
+        let index = nibble(bits, level);
+        let removed = match self.children[index].as_deref_mut() {
+            Some(child) => child.remove(bits, level + 1),
+            None => false,
+        };
+
+        let child_now_empty = matches!(&self.children[index], Some(child) if child.is_empty());
+        if removed && child_now_empty {
+            self.children[index] = None;
+        }
+
+        removed
+    }
+
+    fn is_empty(&self) -> bool {
+        self.leaf.is_none() && self.children.iter().all(Option::is_none)
+    }
+}
+
+enum Storage<K, V> {
+    HashMap(HashMap<K, V>),
+    RadixTrie(RadixNode<K, V>),
+}
+
+impl<K, V> Storage<K, V>
+where
+    K: Eq + Hash + Copy + PackedKey,
+    V: Copy,
+{
+    fn remove(&mut self, key: K) {
+        match self {
+            Storage::HashMap(map) => {
+                map.remove(&key);
+            }
+            Storage::RadixTrie(root) => {
+                root.remove(key.packed_bits(), 0);
+            }
+        }
+    }
+}
+
+pub(crate) struct Cache<K, V>
+where
+    K: Eq + Hash + Copy + PackedKey,
+    V: Copy,
+{
+    storage: Mutex<Storage<K, V>>,
+    // Access order, most-recently-touched at the back, consulted for LRU eviction. May contain
+    // stale duplicate entries for a key that's been accessed more than once, or for a key that's
+    // already been evicted; both are cheaply skipped as they surface at the front.
+    order: Mutex<VecDeque<K>>,
+    max_entries: Mutex<Option<usize>>,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Eq + Hash + Copy + PackedKey,
+    V: Copy,
+{
+    pub(crate) fn new(backend: Backend) -> Self {
+        Self::with_capacity(backend, None)
+    }
+
+    /// As `new`, but evicts the least-recently-touched entry on every insert once the table
+    /// holds more than `max_entries`. Pass `None` for the previous, unbounded behavior.
+    pub(crate) fn with_capacity(backend: Backend, max_entries: Option<usize>) -> Self {
+        let storage = match backend {
+            Backend::HashMap => Storage::HashMap(HashMap::new()),
+            Backend::RadixTrie => Storage::RadixTrie(RadixNode::empty()),
+        };
+
+        Cache {
+            storage: Mutex::new(storage),
+            order: Mutex::new(VecDeque::new()),
+            max_entries: Mutex::new(max_entries),
+        }
+    }
+
+    /// Changes the maximum number of entries this cache will hold going forward, evicting
+    /// immediately if it's already over the new cap.
+    pub(crate) fn set_max_entries(&self, max_entries: Option<usize>) {
+        *self.max_entries.lock().unwrap() = max_entries;
+        self.evict_over_capacity();
+    }
+
+    /// Returns the cached value for `key`, inserting the one built by `default` if there isn't
+    /// one yet.
+    pub(crate) fn get_or_insert_with(&self, key: K, default: impl FnOnce() -> V) -> V {
+        let value = match &mut *self.storage.lock().unwrap() {
+            Storage::HashMap(map) => *map.entry(key).or_insert_with(default),
+            Storage::RadixTrie(root) => root.get_or_insert_with(key.packed_bits(), key, default),
+        };
+
+        self.order.lock().unwrap().push_back(key);
+        self.evict_over_capacity();
+
+        value
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match &*self.storage.lock().unwrap() {
+            Storage::HashMap(map) => map.len(),
+            Storage::RadixTrie(root) => root.len(),
+        }
+    }
+
+    /// A snapshot of every `(key, value)` pair currently held, e.g. for persistence to walk.
+    pub(crate) fn snapshot(&self) -> Vec<(K, V)> {
+        match &*self.storage.lock().unwrap() {
+            Storage::HashMap(map) => map.iter().map(|(&key, &value)| (key, value)).collect(),
+            Storage::RadixTrie(root) => {
+                let mut entries = Vec::new();
+                root.snapshot_into(&mut entries);
+                entries
+            }
+        }
+    }
+
+    // Evicts least-recently-touched entries until at or under the configured cap. Since
+    // `NodeId`s into an append-only arena carry no reachability count, this simply drops the
+    // table entry outright rather than checking whether it's still in use elsewhere.
+    fn evict_over_capacity(&self) {
+        let max_entries = match *self.max_entries.lock().unwrap() {
+            Some(max_entries) => max_entries,
+            None => return,
+        };
+
+        loop {
+            if self.len() <= max_entries {
+                return;
+            }
+
+            let candidate = {
+                let mut order = self.order.lock().unwrap();
+                match order.pop_front() {
+                    // Touched again since: a stale duplicate, not the least-recently-touched.
+                    Some(key) if order.contains(&key) => continue,
+                    Some(key) => key,
+                    None => return,
+                }
+            };
+
+            self.storage.lock().unwrap().remove(candidate);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! A `Sync` drop-in for `std::cell::Cell`'s `get`/`set` surface, for per-node data that may be
+//! read and written by multiple worker threads sharing one `NodeCache` (see `parallel`). Plain
+//! `Cell` can never be `Sync` regardless of `T`, which would make the `NodeCache` it sits inside
+//! unshareable across threads; this trades `Cell`'s free reads/writes for a short-lived `Mutex`
+//! lock per access, while keeping callers (`mcts`, `persistence`) untouched.
+
+use std::sync::Mutex;
+
+pub(crate) struct SyncCell<T>(Mutex<T>);
+
+impl<T> SyncCell<T>
+where
+    T: Copy,
+{
+    pub(crate) fn new(value: T) -> Self {
+        SyncCell(Mutex::new(value))
+    }
+
+    pub(crate) fn get(&self) -> T {
+        *self.0.lock().unwrap()
+    }
+
+    pub(crate) fn set(&self, value: T) {
+        *self.0.lock().unwrap() = value;
+    }
+}